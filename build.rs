@@ -7,7 +7,26 @@ const COMMANDS: &[&str] = &[
   "delete_password",
   "delete_secret",
   "has_password",
-  "has_secret"
+  "has_secret",
+  "set_json",
+  "get_json",
+  "delete_json",
+  "has_json",
+  "get_with_metadata",
+  "set_metadata",
+  "delete_user",
+  "clear_service",
+  "purge_expired",
+  "list_credentials",
+  "generate_secret",
+  "generate_password",
+  "hmac_sha256",
+  "ed25519_sign",
+  "ed25519_public_key",
+  "aes_gcm_encrypt",
+  "aes_gcm_decrypt",
+  "get_audit_events",
+  "migrate_naming"
 ];
 
 fn main() {
@@ -1,30 +1,147 @@
-export type CredentialType = 'Password' | 'Secret';
+import { type UnlistenFn } from '@tauri-apps/api/event';
+export type CredentialType = 'Password' | 'Secret' | 'Json';
 export interface CredentialValue {
-    type: 'Password' | 'Secret';
-    data: string | number[];
+    type: CredentialType;
+    data: string | number[] | unknown;
+}
+export interface CredentialRecord {
+    value: CredentialValue;
+    createdAt: number | null;
+    updatedAt: number | null;
+    label: string | null;
+    comment: string | null;
+    expiresAt: number | null;
+}
+export interface SetOptions {
+    ttl?: number;
+}
+export interface CredentialChange {
+    service: string;
+    username: string | null;
+    credentialType: CredentialType | null;
+    operation: 'set' | 'delete';
+}
+export interface AuditEvent {
+    timestamp: number;
+    window: string;
+    command: string;
+    service: string;
+    username: string | null;
+    success: boolean;
+    error: string | null;
+}
+export interface NamingMigration {
+    dryRun: boolean;
+    moved: [string, CredentialType][];
+    unchanged: [string, CredentialType][];
+    conflicts: [string, CredentialType][];
+}
+export interface PasswordPolicy {
+    length?: number;
+    lowercase?: boolean;
+    uppercase?: boolean;
+    digits?: boolean;
+    symbols?: boolean;
 }
 export declare function initializeKeyring(serviceName: string): Promise<void>;
-export declare function setPassword(username: string, password: string): Promise<void>;
-export declare function getPassword(username: string): Promise<string>;
-export declare function deletePassword(username: string): Promise<void>;
-export declare function hasPassword(username: string): Promise<boolean>;
-export declare function setSecret(username: string, secret: number[]): Promise<void>;
-export declare function getSecret(username: string): Promise<number[]>;
-export declare function deleteSecret(username: string): Promise<void>;
-export declare function hasSecret(username: string): Promise<boolean>;
+export declare function setPassword(username: string, password: string, service?: string, options?: SetOptions): Promise<void>;
+export declare function getPassword(username: string, service?: string): Promise<string>;
+export declare function deletePassword(username: string, service?: string): Promise<void>;
+export declare function hasPassword(username: string, service?: string): Promise<boolean>;
+export declare function generatePassword(username: string, policy?: PasswordPolicy, service?: string): Promise<void>;
+export declare function setSecret(username: string, secret: number[], service?: string, options?: SetOptions): Promise<void>;
+export declare function getSecret(username: string, service?: string): Promise<number[]>;
+export declare function deleteSecret(username: string, service?: string): Promise<void>;
+export declare function hasSecret(username: string, service?: string): Promise<boolean>;
+export declare function generateSecret(username: string, length: number, service?: string): Promise<void>;
+export declare function setJson<T = unknown>(username: string, value: T, service?: string, options?: SetOptions): Promise<void>;
+export declare function getJson<T = unknown>(username: string, service?: string): Promise<T>;
+export declare function deleteJson(username: string, service?: string): Promise<void>;
+export declare function hasJson(username: string, service?: string): Promise<boolean>;
+export declare function getWithMetadata(username: string, credentialType: CredentialType, service?: string): Promise<CredentialRecord>;
+export declare function setMetadata(username: string, credentialType: CredentialType, label?: string, comment?: string, service?: string): Promise<void>;
+export declare function purgeExpired(service?: string): Promise<number>;
+export declare function migrateNaming(dryRun?: boolean, service?: string): Promise<NamingMigration>;
+export declare function deleteUser(username: string, service?: string): Promise<void>;
+export declare function clearService(service?: string): Promise<void>;
+export declare function listCredentials(service?: string): Promise<[string, CredentialType][]>;
+export declare function hmacSha256(username: string, data: number[], service?: string): Promise<number[]>;
+export declare function ed25519Sign(username: string, message: number[], service?: string): Promise<number[]>;
+export declare function ed25519PublicKey(username: string, service?: string): Promise<number[]>;
+export declare function aesGcmEncrypt(username: string, plaintext: number[], aad?: number[], service?: string): Promise<number[]>;
+export declare function aesGcmDecrypt(username: string, ciphertext: number[], aad?: number[], service?: string): Promise<number[]>;
+export interface SecretKey {
+    hmacSha256(data: number[]): Promise<number[]>;
+    ed25519Sign(message: number[]): Promise<number[]>;
+    ed25519PublicKey(): Promise<number[]>;
+    aesGcmEncrypt(plaintext: number[], aad?: number[]): Promise<number[]>;
+    aesGcmDecrypt(ciphertext: number[], aad?: number[]): Promise<number[]>;
+}
+export declare function secretKey(username: string, service?: string): SecretKey;
+export declare function getAuditEvents(limit?: number): Promise<AuditEvent[]>;
+export declare function onCredentialChanged(handler: (change: CredentialChange) => void): Promise<UnlistenFn>;
+export interface ServiceKeyring {
+    list(): Promise<[string, CredentialType][]>;
+    getWithMetadata(username: string, credentialType: CredentialType): Promise<CredentialRecord>;
+    setMetadata(username: string, credentialType: CredentialType, label?: string, comment?: string): Promise<void>;
+    deleteUser(username: string): Promise<void>;
+    clear(): Promise<void>;
+    purgeExpired(): Promise<number>;
+    migrateNaming(dryRun?: boolean): Promise<NamingMigration>;
+    secretKey(username: string): SecretKey;
+    password: {
+        set(username: string, password: string, options?: SetOptions): Promise<void>;
+        generate(username: string, policy?: PasswordPolicy): Promise<void>;
+        get(username: string): Promise<string>;
+        delete(username: string): Promise<void>;
+        exists(username: string): Promise<boolean>;
+    };
+    secret: {
+        set(username: string, secret: number[], options?: SetOptions): Promise<void>;
+        generate(username: string, length: number): Promise<void>;
+        get(username: string): Promise<number[]>;
+        delete(username: string): Promise<void>;
+        exists(username: string): Promise<boolean>;
+    };
+    json: {
+        set<T = unknown>(username: string, value: T, options?: SetOptions): Promise<void>;
+        get<T = unknown>(username: string): Promise<T>;
+        delete(username: string): Promise<void>;
+        exists(username: string): Promise<boolean>;
+    };
+}
+export declare function service(name: string): ServiceKeyring;
 export declare const keyring: {
     initialize: typeof initializeKeyring;
+    service: typeof service;
+    list: typeof listCredentials;
+    getWithMetadata: typeof getWithMetadata;
+    setMetadata: typeof setMetadata;
+    deleteUser: typeof deleteUser;
+    clear: typeof clearService;
+    purgeExpired: typeof purgeExpired;
+    migrateNaming: typeof migrateNaming;
+    secretKey: typeof secretKey;
+    onChanged: typeof onCredentialChanged;
     password: {
         set: typeof setPassword;
+        generate: typeof generatePassword;
         get: typeof getPassword;
         delete: typeof deletePassword;
         exists: typeof hasPassword;
     };
     secret: {
         set: typeof setSecret;
+        generate: typeof generateSecret;
         get: typeof getSecret;
         delete: typeof deleteSecret;
         exists: typeof hasSecret;
     };
+    json: {
+        set: typeof setJson;
+        get: typeof getJson;
+        delete: typeof deleteJson;
+        exists: typeof hasJson;
+    };
 };
 export default keyring;
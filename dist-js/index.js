@@ -1,70 +1,286 @@
 import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
 
-// Initialize the keyring service with a service name
+// Override the service name configured for the plugin (defaults to the app identifier).
+// Only the first call succeeds.
 async function initializeKeyring(serviceName) {
     return await invoke('plugin:keyring|initialize_keyring', {
         serviceName,
     });
 }
 // Password operations
-async function setPassword(username, password) {
+async function setPassword(username, password, service, options) {
     return await invoke('plugin:keyring|set_password', {
         username,
         password,
+        service,
+        ttl: options?.ttl,
     });
 }
-async function getPassword(username) {
+async function getPassword(username, service) {
     return await invoke('plugin:keyring|get_password', {
         username,
+        service,
     });
 }
-async function deletePassword(username) {
+async function deletePassword(username, service) {
     return await invoke('plugin:keyring|delete_password', {
         username,
+        service,
     });
 }
-async function hasPassword(username) {
+async function hasPassword(username, service) {
     return await invoke('plugin:keyring|has_password', {
         username,
+        service,
+    });
+}
+// Store a random password generated in Rust; the value is not returned
+async function generatePassword(username, policy, service) {
+    return await invoke('plugin:keyring|generate_password', {
+        username,
+        policy,
+        service,
     });
 }
 // Secret operations (for binary data)
-async function setSecret(username, secret) {
+async function setSecret(username, secret, service, options) {
     return await invoke('plugin:keyring|set_secret', {
         username,
         secret,
+        service,
+        ttl: options?.ttl,
     });
 }
-async function getSecret(username) {
+async function getSecret(username, service) {
     return await invoke('plugin:keyring|get_secret', {
         username,
+        service,
     });
 }
-async function deleteSecret(username) {
+async function deleteSecret(username, service) {
     return await invoke('plugin:keyring|delete_secret', {
         username,
+        service,
     });
 }
-async function hasSecret(username) {
+async function hasSecret(username, service) {
     return await invoke('plugin:keyring|has_secret', {
         username,
+        service,
+    });
+}
+// Store `length` random bytes generated in Rust; the value is not returned
+async function generateSecret(username, length, service) {
+    return await invoke('plugin:keyring|generate_secret', {
+        username,
+        length,
+        service,
+    });
+}
+// JSON operations (for structured data)
+async function setJson(username, value, service, options) {
+    return await invoke('plugin:keyring|set_json', {
+        username,
+        value,
+        service,
+        ttl: options?.ttl,
+    });
+}
+async function getJson(username, service) {
+    return await invoke('plugin:keyring|get_json', {
+        username,
+        service,
+    });
+}
+async function deleteJson(username, service) {
+    return await invoke('plugin:keyring|delete_json', {
+        username,
+        service,
+    });
+}
+async function hasJson(username, service) {
+    return await invoke('plugin:keyring|has_json', {
+        username,
+        service,
+    });
+}
+// Metadata operations
+async function getWithMetadata(username, credentialType, service) {
+    return await invoke('plugin:keyring|get_with_metadata', {
+        username,
+        credentialType,
+        service,
+    });
+}
+async function setMetadata(username, credentialType, label, comment, service) {
+    return await invoke('plugin:keyring|set_metadata', {
+        username,
+        credentialType,
+        label,
+        comment,
+        service,
+    });
+}
+// Remove every expired credential, returning how many were removed
+async function purgeExpired(service) {
+    return await invoke('plugin:keyring|purge_expired', {
+        service,
+    });
+}
+// Move entries from the legacy layout to the configured naming scheme. With
+// dryRun nothing is changed and the report describes what would be moved.
+async function migrateNaming(dryRun, service) {
+    return await invoke('plugin:keyring|migrate_naming', {
+        dryRun,
+        service,
+    });
+}
+// Bulk removal. Deleting requires the keyring:manage permission set.
+async function deleteUser(username, service) {
+    return await invoke('plugin:keyring|delete_user', {
+        username,
+        service,
+    });
+}
+// Requires the keyring:manage or keyring:allow-clear-service permission
+async function clearService(service) {
+    return await invoke('plugin:keyring|clear_service', {
+        service,
+    });
+}
+// List the stored credentials as [username, type] pairs
+async function listCredentials(service) {
+    return await invoke('plugin:keyring|list_credentials', {
+        service,
+    });
+}
+// Operations that use a stored secret as a key. The secret is read and used in
+// Rust; only the results are returned.
+async function hmacSha256(username, data, service) {
+    return await invoke('plugin:keyring|hmac_sha256', {
+        username,
+        data,
+        service,
+    });
+}
+// The secret must be a 32-byte Ed25519 seed
+async function ed25519Sign(username, message, service) {
+    return await invoke('plugin:keyring|ed25519_sign', {
+        username,
+        message,
+        service,
+    });
+}
+async function ed25519PublicKey(username, service) {
+    return await invoke('plugin:keyring|ed25519_public_key', {
+        username,
+        service,
+    });
+}
+// The secret must be a 32-byte key. Returns the nonce followed by the ciphertext.
+async function aesGcmEncrypt(username, plaintext, aad, service) {
+    return await invoke('plugin:keyring|aes_gcm_encrypt', {
+        username,
+        plaintext,
+        aad,
+        service,
+    });
+}
+async function aesGcmDecrypt(username, ciphertext, aad, service) {
+    return await invoke('plugin:keyring|aes_gcm_decrypt', {
+        username,
+        ciphertext,
+        aad,
+        service,
+    });
+}
+function secretKey(username, service) {
+    return {
+        hmacSha256: (data) => hmacSha256(username, data, service),
+        ed25519Sign: (message) => ed25519Sign(username, message, service),
+        ed25519PublicKey: () => ed25519PublicKey(username, service),
+        aesGcmEncrypt: (plaintext, aad) => aesGcmEncrypt(username, plaintext, aad, service),
+        aesGcmDecrypt: (ciphertext, aad) => aesGcmDecrypt(username, ciphertext, aad, service),
+    };
+}
+// Recent audit events, newest first. Empty unless auditing is enabled.
+// Requires the keyring:allow-get-audit-events permission
+async function getAuditEvents(limit) {
+    return await invoke('plugin:keyring|get_audit_events', {
+        limit,
     });
 }
+// Called in every window whenever a credential is set or deleted, including by
+// this window. Returns a function that stops listening.
+async function onCredentialChanged(handler) {
+    return await listen('keyring://changed', (event) => handler(event.payload));
+}
+function service(name) {
+    return {
+        list: () => listCredentials(name),
+        getWithMetadata: (username, credentialType) => getWithMetadata(username, credentialType, name),
+        setMetadata: (username, credentialType, label, comment) => setMetadata(username, credentialType, label, comment, name),
+        deleteUser: (username) => deleteUser(username, name),
+        clear: () => clearService(name),
+        purgeExpired: () => purgeExpired(name),
+        migrateNaming: (dryRun) => migrateNaming(dryRun, name),
+        secretKey: (username) => secretKey(username, name),
+        password: {
+            set: (username, password, options) => setPassword(username, password, name, options),
+            generate: (username, policy) => generatePassword(username, policy, name),
+            get: (username) => getPassword(username, name),
+            delete: (username) => deletePassword(username, name),
+            exists: (username) => hasPassword(username, name),
+        },
+        secret: {
+            set: (username, secret, options) => setSecret(username, secret, name, options),
+            generate: (username, length) => generateSecret(username, length, name),
+            get: (username) => getSecret(username, name),
+            delete: (username) => deleteSecret(username, name),
+            exists: (username) => hasSecret(username, name),
+        },
+        json: {
+            set: (username, value, options) => setJson(username, value, name, options),
+            get: (username) => getJson(username, name),
+            delete: (username) => deleteJson(username, name),
+            exists: (username) => hasJson(username, name),
+        },
+    };
+}
 // Convenience functions for common use cases
 const keyring = {
     initialize: initializeKeyring,
+    service,
+    list: listCredentials,
+    getWithMetadata,
+    setMetadata,
+    deleteUser,
+    clear: clearService,
+    purgeExpired,
+    migrateNaming,
+    secretKey,
+    onChanged: onCredentialChanged,
     password: {
         set: setPassword,
+        generate: generatePassword,
         get: getPassword,
         delete: deletePassword,
         exists: hasPassword,
     },
     secret: {
         set: setSecret,
+        generate: generateSecret,
         get: getSecret,
         delete: deleteSecret,
         exists: hasSecret,
     },
+    json: {
+        set: setJson,
+        get: getJson,
+        delete: deleteJson,
+        exists: hasJson,
+    },
 };
 
-export { keyring as default, deletePassword, deleteSecret, getPassword, getSecret, hasPassword, hasSecret, initializeKeyring, keyring, setPassword, setSecret };
+export { aesGcmDecrypt, aesGcmEncrypt, clearService, keyring as default, deleteJson, deletePassword, deleteSecret, deleteUser, ed25519PublicKey, ed25519Sign, generatePassword, generateSecret, getAuditEvents, getJson, getPassword, getSecret, getWithMetadata, hasJson, hasPassword, hasSecret, hmacSha256, initializeKeyring, keyring, listCredentials, migrateNaming, onCredentialChanged, purgeExpired, secretKey, service, setJson, setMetadata, setPassword, setSecret };
@@ -1,14 +1,72 @@
 import { invoke } from '@tauri-apps/api/core'
+import { listen, type UnlistenFn } from '@tauri-apps/api/event'
 
 // TypeScript types for the keyring plugin
-export type CredentialType = 'Password' | 'Secret'
+export type CredentialType = 'Password' | 'Secret' | 'Json'
 
 export interface CredentialValue {
-  type: 'Password' | 'Secret'
-  data: string | number[]
+  type: CredentialType
+  data: string | number[] | unknown
 }
 
-// Initialize the keyring service with a service name
+// A credential with its metadata. Timestamps are seconds since the Unix epoch,
+// null for entries written before metadata was recorded
+export interface CredentialRecord {
+  value: CredentialValue
+  createdAt: number | null
+  updatedAt: number | null
+  label: string | null
+  comment: string | null
+  expiresAt: number | null
+}
+
+// Options for storing a credential
+export interface SetOptions {
+  // Seconds until the credential expires and reads as missing
+  ttl?: number
+}
+
+// Character classes and length of a generated password. Defaults to 24
+// characters drawn from every class.
+export interface PasswordPolicy {
+  length?: number
+  lowercase?: boolean
+  uppercase?: boolean
+  digits?: boolean
+  symbols?: boolean
+}
+
+// Payload of the keyring://changed event. Every window receives it, so username
+// is hashed, and null when every user's credentials changed; credentialType is
+// null when several types changed.
+export interface CredentialChange {
+  service: string
+  username: string | null
+  credentialType: CredentialType | null
+  operation: 'set' | 'delete'
+}
+
+// A recorded command invocation. Usernames are hashed.
+export interface AuditEvent {
+  timestamp: number
+  window: string
+  command: string
+  service: string
+  username: string | null
+  success: boolean
+  error: string | null
+}
+
+// Outcome of moving entries to the configured naming scheme, as [username, type] pairs
+export interface NamingMigration {
+  dryRun: boolean
+  moved: [string, CredentialType][]
+  unchanged: [string, CredentialType][]
+  conflicts: [string, CredentialType][]
+}
+
+// Confirm the service name configured for the plugin (defaults to the app identifier).
+// Kept for compatibility: any other name is rejected, set it with `service` instead.
 export async function initializeKeyring(serviceName: string): Promise<void> {
   return await invoke('plugin:keyring|initialize_keyring', {
     serviceName,
@@ -16,72 +74,391 @@ export async function initializeKeyring(serviceName: string): Promise<void> {
 }
 
 // Password operations
-export async function setPassword(username: string, password: string): Promise<void> {
+export async function setPassword(
+  username: string,
+  password: string,
+  service?: string,
+  options?: SetOptions
+): Promise<void> {
   return await invoke('plugin:keyring|set_password', {
     username,
     password,
+    service,
+    ttl: options?.ttl,
   })
 }
 
-export async function getPassword(username: string): Promise<string> {
+export async function getPassword(username: string, service?: string): Promise<string> {
   return await invoke('plugin:keyring|get_password', {
     username,
+    service,
   })
 }
 
-export async function deletePassword(username: string): Promise<void> {
+export async function deletePassword(username: string, service?: string): Promise<void> {
   return await invoke('plugin:keyring|delete_password', {
     username,
+    service,
   })
 }
 
-export async function hasPassword(username: string): Promise<boolean> {
+export async function hasPassword(username: string, service?: string): Promise<boolean> {
   return await invoke('plugin:keyring|has_password', {
     username,
+    service,
+  })
+}
+
+// Store a random password generated in Rust; the value is not returned
+export async function generatePassword(
+  username: string,
+  policy?: PasswordPolicy,
+  service?: string
+): Promise<void> {
+  return await invoke('plugin:keyring|generate_password', {
+    username,
+    policy,
+    service,
   })
 }
 
 // Secret operations (for binary data)
-export async function setSecret(username: string, secret: number[]): Promise<void> {
+export async function setSecret(
+  username: string,
+  secret: number[],
+  service?: string,
+  options?: SetOptions
+): Promise<void> {
   return await invoke('plugin:keyring|set_secret', {
     username,
     secret,
+    service,
+    ttl: options?.ttl,
   })
 }
 
-export async function getSecret(username: string): Promise<number[]> {
+export async function getSecret(username: string, service?: string): Promise<number[]> {
   return await invoke('plugin:keyring|get_secret', {
     username,
+    service,
   })
 }
 
-export async function deleteSecret(username: string): Promise<void> {
+export async function deleteSecret(username: string, service?: string): Promise<void> {
   return await invoke('plugin:keyring|delete_secret', {
     username,
+    service,
   })
 }
 
-export async function hasSecret(username: string): Promise<boolean> {
+export async function hasSecret(username: string, service?: string): Promise<boolean> {
   return await invoke('plugin:keyring|has_secret', {
     username,
+    service,
+  })
+}
+
+// Store `length` random bytes generated in Rust; the value is not returned
+export async function generateSecret(username: string, length: number, service?: string): Promise<void> {
+  return await invoke('plugin:keyring|generate_secret', {
+    username,
+    length,
+    service,
+  })
+}
+
+// JSON operations (for structured data)
+export async function setJson<T = unknown>(
+  username: string,
+  value: T,
+  service?: string,
+  options?: SetOptions
+): Promise<void> {
+  return await invoke('plugin:keyring|set_json', {
+    username,
+    value,
+    service,
+    ttl: options?.ttl,
+  })
+}
+
+export async function getJson<T = unknown>(username: string, service?: string): Promise<T> {
+  return await invoke('plugin:keyring|get_json', {
+    username,
+    service,
+  })
+}
+
+export async function deleteJson(username: string, service?: string): Promise<void> {
+  return await invoke('plugin:keyring|delete_json', {
+    username,
+    service,
+  })
+}
+
+export async function hasJson(username: string, service?: string): Promise<boolean> {
+  return await invoke('plugin:keyring|has_json', {
+    username,
+    service,
+  })
+}
+
+// Metadata operations
+export async function getWithMetadata(
+  username: string,
+  credentialType: CredentialType,
+  service?: string
+): Promise<CredentialRecord> {
+  return await invoke('plugin:keyring|get_with_metadata', {
+    username,
+    credentialType,
+    service,
+  })
+}
+
+export async function setMetadata(
+  username: string,
+  credentialType: CredentialType,
+  label?: string,
+  comment?: string,
+  service?: string
+): Promise<void> {
+  return await invoke('plugin:keyring|set_metadata', {
+    username,
+    credentialType,
+    label,
+    comment,
+    service,
+  })
+}
+
+// Remove every expired credential, returning how many were removed
+export async function purgeExpired(service?: string): Promise<number> {
+  return await invoke('plugin:keyring|purge_expired', {
+    service,
+  })
+}
+
+// Move entries from the legacy layout to the configured naming scheme. With
+// dryRun nothing is changed and the report describes what would be moved.
+export async function migrateNaming(dryRun?: boolean, service?: string): Promise<NamingMigration> {
+  return await invoke('plugin:keyring|migrate_naming', {
+    dryRun,
+    service,
+  })
+}
+
+// Bulk removal. Deleting requires the keyring:manage permission set.
+export async function deleteUser(username: string, service?: string): Promise<void> {
+  return await invoke('plugin:keyring|delete_user', {
+    username,
+    service,
+  })
+}
+
+// Requires the keyring:manage or keyring:allow-clear-service permission
+export async function clearService(service?: string): Promise<void> {
+  return await invoke('plugin:keyring|clear_service', {
+    service,
+  })
+}
+
+// List the stored credentials as [username, type] pairs
+export async function listCredentials(service?: string): Promise<[string, CredentialType][]> {
+  return await invoke('plugin:keyring|list_credentials', {
+    service,
+  })
+}
+
+// Operations that use a stored secret as a key. The secret is read and used in
+// Rust; only the results are returned.
+export async function hmacSha256(username: string, data: number[], service?: string): Promise<number[]> {
+  return await invoke('plugin:keyring|hmac_sha256', {
+    username,
+    data,
+    service,
+  })
+}
+
+// The secret must be a 32-byte Ed25519 seed
+export async function ed25519Sign(username: string, message: number[], service?: string): Promise<number[]> {
+  return await invoke('plugin:keyring|ed25519_sign', {
+    username,
+    message,
+    service,
+  })
+}
+
+export async function ed25519PublicKey(username: string, service?: string): Promise<number[]> {
+  return await invoke('plugin:keyring|ed25519_public_key', {
+    username,
+    service,
   })
 }
 
+// The secret must be a 32-byte key. Returns the nonce followed by the ciphertext.
+export async function aesGcmEncrypt(
+  username: string,
+  plaintext: number[],
+  aad?: number[],
+  service?: string
+): Promise<number[]> {
+  return await invoke('plugin:keyring|aes_gcm_encrypt', {
+    username,
+    plaintext,
+    aad,
+    service,
+  })
+}
+
+export async function aesGcmDecrypt(
+  username: string,
+  ciphertext: number[],
+  aad?: number[],
+  service?: string
+): Promise<number[]> {
+  return await invoke('plugin:keyring|aes_gcm_decrypt', {
+    username,
+    ciphertext,
+    aad,
+    service,
+  })
+}
+
+// A handle to a stored secret that can be used as a key without reading it
+export interface SecretKey {
+  hmacSha256(data: number[]): Promise<number[]>
+  ed25519Sign(message: number[]): Promise<number[]>
+  ed25519PublicKey(): Promise<number[]>
+  aesGcmEncrypt(plaintext: number[], aad?: number[]): Promise<number[]>
+  aesGcmDecrypt(ciphertext: number[], aad?: number[]): Promise<number[]>
+}
+
+export function secretKey(username: string, service?: string): SecretKey {
+  return {
+    hmacSha256: (data) => hmacSha256(username, data, service),
+    ed25519Sign: (message) => ed25519Sign(username, message, service),
+    ed25519PublicKey: () => ed25519PublicKey(username, service),
+    aesGcmEncrypt: (plaintext, aad) => aesGcmEncrypt(username, plaintext, aad, service),
+    aesGcmDecrypt: (ciphertext, aad) => aesGcmDecrypt(username, ciphertext, aad, service),
+  }
+}
+
+// Recent audit events, newest first. Empty unless auditing is enabled.
+// Requires the keyring:allow-get-audit-events permission
+export async function getAuditEvents(limit?: number): Promise<AuditEvent[]> {
+  return await invoke('plugin:keyring|get_audit_events', {
+    limit,
+  })
+}
+
+// Called in every window whenever a credential is set or deleted, including by
+// this window. Returns a function that stops listening.
+export async function onCredentialChanged(
+  handler: (change: CredentialChange) => void
+): Promise<UnlistenFn> {
+  return await listen<CredentialChange>('keyring://changed', (event) => handler(event.payload))
+}
+
+// Operations scoped to a separate named service
+export interface ServiceKeyring {
+  list(): Promise<[string, CredentialType][]>
+  getWithMetadata(username: string, credentialType: CredentialType): Promise<CredentialRecord>
+  setMetadata(username: string, credentialType: CredentialType, label?: string, comment?: string): Promise<void>
+  deleteUser(username: string): Promise<void>
+  clear(): Promise<void>
+  purgeExpired(): Promise<number>
+  migrateNaming(dryRun?: boolean): Promise<NamingMigration>
+  secretKey(username: string): SecretKey
+  password: {
+    set(username: string, password: string, options?: SetOptions): Promise<void>
+    generate(username: string, policy?: PasswordPolicy): Promise<void>
+    get(username: string): Promise<string>
+    delete(username: string): Promise<void>
+    exists(username: string): Promise<boolean>
+  }
+  secret: {
+    set(username: string, secret: number[], options?: SetOptions): Promise<void>
+    generate(username: string, length: number): Promise<void>
+    get(username: string): Promise<number[]>
+    delete(username: string): Promise<void>
+    exists(username: string): Promise<boolean>
+  }
+  json: {
+    set<T = unknown>(username: string, value: T, options?: SetOptions): Promise<void>
+    get<T = unknown>(username: string): Promise<T>
+    delete(username: string): Promise<void>
+    exists(username: string): Promise<boolean>
+  }
+}
+
+export function service(name: string): ServiceKeyring {
+  return {
+    list: () => listCredentials(name),
+    getWithMetadata: (username, credentialType) => getWithMetadata(username, credentialType, name),
+    setMetadata: (username, credentialType, label, comment) =>
+      setMetadata(username, credentialType, label, comment, name),
+    deleteUser: (username) => deleteUser(username, name),
+    clear: () => clearService(name),
+    purgeExpired: () => purgeExpired(name),
+    migrateNaming: (dryRun?: boolean) => migrateNaming(dryRun, name),
+    secretKey: (username) => secretKey(username, name),
+    password: {
+      set: (username, password, options) => setPassword(username, password, name, options),
+      generate: (username, policy) => generatePassword(username, policy, name),
+      get: (username) => getPassword(username, name),
+      delete: (username) => deletePassword(username, name),
+      exists: (username) => hasPassword(username, name),
+    },
+    secret: {
+      set: (username, secret, options) => setSecret(username, secret, name, options),
+      generate: (username, length) => generateSecret(username, length, name),
+      get: (username) => getSecret(username, name),
+      delete: (username) => deleteSecret(username, name),
+      exists: (username) => hasSecret(username, name),
+    },
+    json: {
+      set: (username, value, options) => setJson(username, value, name, options),
+      get: <T>(username: string) => getJson<T>(username, name),
+      delete: (username) => deleteJson(username, name),
+      exists: (username) => hasJson(username, name),
+    },
+  }
+}
+
 // Convenience functions for common use cases
 export const keyring = {
   initialize: initializeKeyring,
+  service,
+  list: listCredentials,
+  getWithMetadata,
+  setMetadata,
+  deleteUser,
+  clear: clearService,
+  purgeExpired,
+  migrateNaming,
+  secretKey,
+  onChanged: onCredentialChanged,
   password: {
     set: setPassword,
+    generate: generatePassword,
     get: getPassword,
     delete: deletePassword,
     exists: hasPassword,
   },
   secret: {
     set: setSecret,
+    generate: generateSecret,
     get: getSecret,
     delete: deleteSecret,
     exists: hasSecret,
   },
+  json: {
+    set: setJson,
+    get: getJson,
+    delete: deleteJson,
+    exists: hasJson,
+  },
 }
 
 export default keyring
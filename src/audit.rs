@@ -0,0 +1,208 @@
+//! Audit trail of the commands the webview invokes.
+//!
+//! Each event is kept in a bounded in-memory buffer for [`AuditLog::recent`],
+//! and optionally appended to a JSON Lines file and passed to a callback.
+
+use std::collections::VecDeque;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use log::LevelFilter;
+use serde::{Deserialize, Serialize};
+
+use crate::metadata;
+use crate::redaction::RedactionKey;
+
+/// Number of events kept in memory
+pub(crate) const RECENT_EVENTS: usize = 256;
+
+/// One command invocation.
+///
+/// Usernames are recorded as a short keyed hash, the same as in log output;
+/// credential values are never recorded.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditEvent {
+    /// Seconds since the Unix epoch
+    pub timestamp: u64,
+    /// Label of the window the command came from
+    pub window: String,
+    pub command: String,
+    pub service: String,
+    pub username: Option<String>,
+    pub success: bool,
+    /// The error type, e.g. `EntryNotFound`, when the command failed
+    pub error: Option<String>,
+}
+
+impl AuditEvent {
+    pub(crate) fn new<T>(
+        window: &str,
+        command: &str,
+        service: &str,
+        username: Option<&str>,
+        redaction_key: &RedactionKey,
+        result: &crate::Result<T>,
+    ) -> Self {
+        Self {
+            timestamp: metadata::now(),
+            window: window.to_string(),
+            command: command.to_string(),
+            service: service.to_string(),
+            username: username.and_then(|username| redaction_key.hash(username)),
+            success: result.is_ok(),
+            error: result.as_ref().err().map(error_type),
+        }
+    }
+}
+
+/// The variant name an error is serialized with, without its message
+fn error_type(error: &crate::Error) -> String {
+    serde_json::to_value(error)
+        .ok()
+        .and_then(|value| value.get("type")?.as_str().map(str::to_string))
+        .unwrap_or_else(|| "Unknown".to_string())
+}
+
+/// Receives every audit event as it is recorded
+#[derive(Clone)]
+pub(crate) struct AuditCallback(pub Arc<dyn Fn(&AuditEvent) + Send + Sync>);
+
+impl std::fmt::Debug for AuditCallback {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("AuditCallback")
+    }
+}
+
+/// Where audit events go, managed as plugin state while auditing is enabled
+pub(crate) struct AuditLog {
+    file: Option<Mutex<File>>,
+    callback: Option<AuditCallback>,
+    recent: Mutex<VecDeque<AuditEvent>>,
+    log_level: LevelFilter,
+}
+
+impl AuditLog {
+    /// Open the log, appending to `path` when given
+    pub(crate) fn new(path: Option<&Path>, callback: Option<AuditCallback>, log_level: LevelFilter) -> crate::Result<Self> {
+        let file = match path {
+            Some(path) => {
+                if let Some(parent) = path.parent() {
+                    std::fs::create_dir_all(parent)?;
+                }
+                Some(Mutex::new(OpenOptions::new().create(true).append(true).open(path)?))
+            },
+            None => None,
+        };
+        Ok(Self {
+            file,
+            callback,
+            recent: Mutex::new(VecDeque::with_capacity(RECENT_EVENTS)),
+            log_level,
+        })
+    }
+
+    pub(crate) fn record(&self, event: AuditEvent) {
+        if let Some(file) = &self.file {
+            if let Err(e) = append(&mut file.lock().unwrap(), &event) {
+                keyring_log!(level = self.log_level, Warn, "Failed to write audit event: {}", e);
+            }
+        }
+        if let Some(AuditCallback(callback)) = &self.callback {
+            callback(&event);
+        }
+
+        let mut recent = self.recent.lock().unwrap();
+        if recent.len() == RECENT_EVENTS {
+            recent.pop_front();
+        }
+        recent.push_back(event);
+    }
+
+    /// The most recent events, newest first
+    pub(crate) fn recent(&self, limit: Option<usize>) -> Vec<AuditEvent> {
+        let recent = self.recent.lock().unwrap();
+        recent.iter().rev().take(limit.unwrap_or(RECENT_EVENTS)).cloned().collect()
+    }
+}
+
+/// Write one event as a line of JSON
+fn append(file: &mut File, event: &AuditEvent) -> crate::Result<()> {
+    let mut line = serde_json::to_string(event)?;
+    line.push('\n');
+    file.write_all(line.as_bytes())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn event(command: &str, result: crate::Result<()>) -> AuditEvent {
+        AuditEvent::new("main", command, "com.test.audit", Some("alice"), &redaction_key(), &result)
+    }
+
+    /// One key for every event, like the plugin's per-install key
+    fn redaction_key() -> RedactionKey {
+        static KEY: std::sync::OnceLock<RedactionKey> = std::sync::OnceLock::new();
+        KEY.get_or_init(RedactionKey::generate).clone()
+    }
+
+    #[test]
+    fn test_audit_event_fields() {
+        let ok = event("get_password", Ok(()));
+        assert!(ok.success);
+        assert_eq!(ok.error, None);
+        assert_eq!(ok.username.as_deref(), redaction_key().hash("alice").as_deref());
+
+        let failed = event("get_password", Err(crate::Error::EntryNotFound));
+        assert!(!failed.success);
+        assert_eq!(failed.error.as_deref(), Some("EntryNotFound"));
+
+        let invalid = event("set_secret", Err(crate::Error::InvalidInput("details".into())));
+        assert_eq!(invalid.error.as_deref(), Some("InvalidInput"));
+    }
+
+    #[test]
+    fn test_audit_log_sinks() {
+        let path = std::env::temp_dir().join(format!("keyring-audit-{}/audit.log", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = calls.clone();
+        let callback = AuditCallback(Arc::new(move |_: &AuditEvent| {
+            counter.fetch_add(1, Ordering::SeqCst);
+        }));
+        let log = AuditLog::new(Some(&path), Some(callback), LevelFilter::Off).unwrap();
+
+        log.record(event("set_password", Ok(())));
+        log.record(event("get_password", Err(crate::Error::EntryNotFound)));
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+        let recent = log.recent(None);
+        assert_eq!(recent.len(), 2);
+        assert_eq!(recent[0].command, "get_password");
+        assert_eq!(log.recent(Some(1)).len(), 1);
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<AuditEvent> = contents.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+        assert_eq!(lines, vec![recent[1].clone(), recent[0].clone()]);
+        assert!(!contents.contains("alice"));
+
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_audit_log_is_bounded() {
+        let log = AuditLog::new(None, None, LevelFilter::Off).unwrap();
+        for i in 0..RECENT_EVENTS + 10 {
+            log.record(event(&format!("command_{}", i), Ok(())));
+        }
+        let recent = log.recent(None);
+        assert_eq!(recent.len(), RECENT_EVENTS);
+        assert_eq!(recent[0].command, format!("command_{}", RECENT_EVENTS + 9));
+    }
+}
@@ -0,0 +1,221 @@
+//! Values split across several entries when they are too large for one.
+//!
+//! The credential entry then holds a small manifest, `KRS1m:` followed by
+//! JSON, and the value itself is stored in numbered companion entries. The
+//! manifest records a SHA-256 digest so a partially overwritten value is
+//! reported instead of being reassembled from mismatched chunks.
+
+use keyring_core::Entry;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use zeroize::Zeroizing;
+
+use crate::implementation::new_entry;
+use crate::models::{AccessPolicy, StoreBackend};
+
+/// Marks a credential entry holding a manifest instead of a value
+const MANIFEST_HEADER: &[u8] = b"KRS1m:";
+
+/// Suffix of the companion entries holding the chunks, followed by their index
+const CHUNK_SUFFIX: &str = ".chunk";
+
+/// Largest chunk written, whatever chunk size is configured
+const MAX_CHUNK_SIZE: usize = 1024 * 1024;
+
+/// Largest value split across chunks. Manifests are read from the store, so
+/// their length is checked against this before anything is allocated.
+const MAX_VALUE_LEN: usize = 16 * 1024 * 1024;
+
+/// Where a split value's chunks are and how to check them
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct Manifest {
+    pub chunks: usize,
+    pub length: usize,
+    pub sha256: String,
+}
+
+impl Manifest {
+    /// The manifest as written to the credential entry
+    pub(crate) fn to_bytes(&self) -> crate::Result<Vec<u8>> {
+        let mut bytes = MANIFEST_HEADER.to_vec();
+        serde_json::to_writer(&mut bytes, self)?;
+        Ok(bytes)
+    }
+}
+
+/// The chunk size used when none is configured: values are split only where
+/// the store is known to reject large ones
+pub(crate) fn default_size(backend: StoreBackend) -> Option<usize> {
+    match backend {
+        // Credential Manager caps blobs at 2560 bytes, and stores passwords as
+        // UTF-16, which takes at most twice their UTF-8 length
+        StoreBackend::Native if cfg!(windows) => Some(1024),
+        _ => None,
+    }
+}
+
+/// Whether stored bytes are a manifest rather than a value. Values that look
+/// like one are always split, so this never misreads a value.
+pub(crate) fn is_manifest(stored: &[u8]) -> bool {
+    stored.starts_with(MANIFEST_HEADER)
+}
+
+/// The manifest stored bytes hold, if they are one
+pub(crate) fn manifest(stored: &[u8]) -> crate::Result<Option<Manifest>> {
+    match stored.strip_prefix(MANIFEST_HEADER) {
+        Some(json) => serde_json::from_slice(json)
+            .map(Some)
+            .map_err(|e| crate::Error::InvalidInput(format!("Corrupt chunk manifest: {}", e))),
+        None => Ok(None),
+    }
+}
+
+/// How many chunks the value stored bytes stand for is split across: none
+/// unless they are a manifest, and unknown if they are a corrupt one
+pub(crate) fn chunk_count(stored: &[u8]) -> Option<usize> {
+    manifest(stored).ok().map(|manifest| manifest.map_or(0, |manifest| manifest.chunks))
+}
+
+/// The companion entries a credential's chunks are stored in
+pub(crate) struct ChunkEntries {
+    service: String,
+    entry_username: String,
+    access_policy: Option<AccessPolicy>,
+}
+
+impl ChunkEntries {
+    /// `entry_username` is the full `service/username/type` of the credential,
+    /// or the name of the internal entry being split
+    /// `access_policy` protects the chunks the same way as the entry they belong to
+    pub(crate) fn new(service: &str, entry_username: &str, access_policy: Option<AccessPolicy>) -> Self {
+        Self { service: service.to_string(), entry_username: entry_username.to_string(), access_policy }
+    }
+
+    /// Write `value` through `write_entry`, first splitting it across the chunk
+    /// entries when it is larger than `size` bytes. Returns how many chunks were written.
+    ///
+    /// `previous_chunks` is how many chunks the value being replaced had, if
+    /// known; otherwise the store is probed for chunks left over from it.
+    pub(crate) fn store(
+        &self,
+        value: &[u8],
+        size: Option<usize>,
+        previous_chunks: Option<usize>,
+        write_entry: impl FnOnce(&[u8]) -> crate::Result<()>,
+    ) -> crate::Result<usize> {
+        let size = size.unwrap_or(MAX_CHUNK_SIZE).min(MAX_CHUNK_SIZE);
+        let chunks = if value.len() > size || is_manifest(value) {
+            let manifest = self.write(value, size)?;
+            write_entry(&manifest.to_bytes()?)?;
+            manifest.chunks
+        } else {
+            write_entry(value)?;
+            0
+        };
+        // Chunks beyond these are left over from a larger value written before
+        self.delete_from(chunks, previous_chunks)?;
+        Ok(chunks)
+    }
+
+    /// The value stored bytes read from the credential entry stand for,
+    /// reassembled if they are a manifest
+    pub(crate) fn load(&self, stored: Zeroizing<Vec<u8>>) -> crate::Result<Zeroizing<Vec<u8>>> {
+        match manifest(&stored)? {
+            Some(manifest) => self.read(&manifest),
+            None => Ok(stored),
+        }
+    }
+
+    fn entry(&self, index: usize) -> crate::Result<Entry> {
+        new_entry(&self.service, &format!("{}{}{}", self.entry_username, CHUNK_SUFFIX, index), self.access_policy)
+    }
+
+    /// Store `value` in chunks of at most `size` bytes, returning the manifest
+    /// to write to the credential entry once every chunk is in place
+    fn write(&self, value: &[u8], size: usize) -> crate::Result<Manifest> {
+        if value.len() > MAX_VALUE_LEN {
+            return Err(crate::Error::InvalidInput(format!("Values larger than {} bytes cannot be stored", MAX_VALUE_LEN)));
+        }
+        let mut chunks = 0;
+        for (index, chunk) in value.chunks(size.max(1)).enumerate() {
+            self.entry(index)?.set_secret(chunk)?;
+            chunks += 1;
+        }
+        Ok(Manifest { chunks, length: value.len(), sha256: digest(value) })
+    }
+
+    /// Reassemble the value a manifest describes
+    fn read(&self, manifest: &Manifest) -> crate::Result<Zeroizing<Vec<u8>>> {
+        if manifest.length > MAX_VALUE_LEN || manifest.length > manifest.chunks.saturating_mul(MAX_CHUNK_SIZE) {
+            return Err(crate::Error::InvalidInput("Corrupt chunk manifest: length out of range".into()));
+        }
+        let mut value = Zeroizing::new(Vec::with_capacity(manifest.length));
+        for index in 0..manifest.chunks {
+            let chunk = match self.entry(index)?.get_secret() {
+                Ok(chunk) => Zeroizing::new(chunk),
+                Err(keyring_core::Error::NoEntry) => return Err(incomplete()),
+                Err(e) => return Err(e.into()),
+            };
+            if value.len() + chunk.len() > manifest.length {
+                return Err(incomplete());
+            }
+            value.extend_from_slice(&chunk);
+        }
+        if value.len() != manifest.length || digest(&value) != manifest.sha256 {
+            return Err(incomplete());
+        }
+        Ok(value)
+    }
+
+    /// Delete the chunks from `start` on: up to `chunks` when the number stored
+    /// is known, otherwise until the first one missing
+    pub(crate) fn delete_from(&self, start: usize, chunks: Option<usize>) -> crate::Result<()> {
+        for index in start..chunks.unwrap_or(usize::MAX) {
+            match self.entry(index)?.delete_credential() {
+                Ok(()) => {},
+                Err(keyring_core::Error::NoEntry) if chunks.is_none() => break,
+                Err(keyring_core::Error::NoEntry) => {},
+                Err(e) => return Err(e.into()),
+            }
+        }
+        Ok(())
+    }
+}
+
+fn digest(value: &[u8]) -> String {
+    Sha256::digest(value).iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn incomplete() -> crate::Error {
+    crate::Error::InvalidInput("Chunked value is incomplete or was partially overwritten".into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_manifest_round_trip() {
+        let manifest = Manifest { chunks: 3, length: 2500, sha256: digest(b"value") };
+        let bytes = manifest.to_bytes().unwrap();
+        assert!(is_manifest(&bytes));
+        assert_eq!(super::manifest(&bytes).unwrap(), Some(manifest));
+        assert_eq!(super::manifest(b"KRS1r:value").unwrap(), None);
+        assert!(matches!(super::manifest(b"KRS1m:{"), Err(crate::Error::InvalidInput(_))));
+
+        assert_eq!(chunk_count(&bytes), Some(3));
+        assert_eq!(chunk_count(b"KRS1r:value"), Some(0));
+        assert_eq!(chunk_count(b"KRS1m:{"), None);
+    }
+
+    #[test]
+    fn test_manifest_length_is_checked_before_reading() {
+        // Rejected before any chunk entry is touched, so no store is needed
+        let entries = ChunkEntries::new("com.example.app", "com.example.app/alice/secret", None);
+        for (chunks, length) in [(1, usize::MAX), (2, MAX_CHUNK_SIZE * 2 + 1), (usize::MAX, MAX_VALUE_LEN + 1)] {
+            let manifest = Manifest { chunks, length, sha256: digest(b"") };
+            let stored = Zeroizing::new(manifest.to_bytes().unwrap());
+            assert!(matches!(entries.load(stored), Err(crate::Error::InvalidInput(_))));
+        }
+    }
+}
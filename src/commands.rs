@@ -1,93 +1,524 @@
-use tauri::{AppHandle, command, Runtime};
+use std::sync::Arc;
+use std::time::Duration;
 
+use tauri::ipc::{CommandScope, GlobalScope};
+use tauri::{AppHandle, command, Manager, Runtime, Window};
+use zeroize::Zeroizing;
+
+use crate::audit::{AuditEvent, AuditLog};
 use crate::models::*;
-use crate::Result;
+use crate::redaction::RedactionKey;
+use crate::scope::{ScopeEntry, UsernameScope};
+use crate::service::ServiceHandle;
+use crate::{Error, Result};
 use crate::KeyringExt;
 
+/// Resolve the service a command operates on, defaulting to the configured one
+/// and rejecting services the app has not allowed
+fn service_handle<'a, R: Runtime>(app: &'a AppHandle<R>, service: Option<&str>) -> Result<ServiceHandle<'a, R>> {
+    app.keyring().frontend_service(service)
+}
+
+/// The username patterns the calling window's capabilities allow and deny for a command
+fn username_scope(command_scope: &CommandScope<ScopeEntry>, global_scope: &GlobalScope<ScopeEntry>) -> UsernameScope {
+    fn usernames(entries: &[Arc<ScopeEntry>]) -> impl Iterator<Item = String> + '_ {
+        entries.iter().map(|entry| entry.username.clone())
+    }
+    UsernameScope::new(
+        usernames(command_scope.allows()).chain(usernames(global_scope.allows())).collect(),
+        usernames(command_scope.denies()).chain(usernames(global_scope.denies())).collect(),
+    )
+}
+
+/// Resolve the service a command on `username` operates on, rejecting
+/// usernames outside the caller's scope before the store is touched
+fn scoped_handle<'a, R: Runtime>(
+    app: &'a AppHandle<R>,
+    service: Option<&str>,
+    username: &str,
+    command_scope: &CommandScope<ScopeEntry>,
+    global_scope: &GlobalScope<ScopeEntry>,
+) -> Result<ServiceHandle<'a, R>> {
+    username_scope(command_scope, global_scope).check(username)?;
+    service_handle(app, service)
+}
+
+/// Run a command's operation, recording its outcome when auditing is enabled
+fn audit<R: Runtime, T>(
+    app: &AppHandle<R>,
+    window: &Window<R>,
+    command: &str,
+    service: Option<&str>,
+    username: Option<&str>,
+    operation: impl FnOnce() -> Result<T>,
+) -> Result<T> {
+    let result = operation();
+    if let Some(log) = app.try_state::<AuditLog>() {
+        let service = service.map(str::to_string).unwrap_or_else(|| app.keyring().service_name());
+        let redaction_key = app.state::<RedactionKey>();
+        log.record(AuditEvent::new(window.label(), command, &service, username, &redaction_key, &result));
+    }
+    result
+}
+
+/// Store a credential, expiring it after `ttl` seconds when given
+fn set_credential<R: Runtime>(
+    handle: ServiceHandle<'_, R>,
+    username: &str,
+    credential_type: CredentialType,
+    value: CredentialValue,
+    ttl: Option<u64>,
+) -> Result<()> {
+    match ttl {
+        Some(seconds) => handle.set_with_ttl(username, credential_type, value, Duration::from_secs(seconds)),
+        None => handle.set(username, credential_type, value),
+    }
+}
+
 #[command]
 pub(crate) async fn initialize_keyring<R: Runtime>(
     app: AppHandle<R>,
+    window: Window<R>,
     service_name: String,
 ) -> Result<()> {
-    app.keyring().initialize_service(service_name)
+    audit(&app, &window, "initialize_keyring", None, None, || {
+        // Kept for frontends written when the service had to be set from
+        // JavaScript; only the name already in use is accepted
+        if service_name != app.keyring().service_name() {
+            return Err(Error::ServiceNotAllowed(service_name));
+        }
+        Ok(())
+    })
 }
 
 #[command]
+#[allow(clippy::too_many_arguments)]
 pub(crate) async fn set_password<R: Runtime>(
     app: AppHandle<R>,
+    window: Window<R>,
     username: String,
     password: String,
+    service: Option<String>,
+    ttl: Option<u64>,
+    command_scope: CommandScope<ScopeEntry>,
+    global_scope: GlobalScope<ScopeEntry>,
 ) -> Result<()> {
-    app.keyring().set(
-        &username, 
-        CredentialType::Password, 
-        CredentialValue::Password(password)
-    )
+    audit(&app, &window, "set_password", service.as_deref(), Some(username.as_str()), || {
+        set_credential(
+            scoped_handle(&app, service.as_deref(), &username, &command_scope, &global_scope)?,
+            &username,
+            CredentialType::Password,
+            CredentialValue::Password(password.into()),
+            ttl,
+        )
+    })
 }
 
 #[command]
+#[allow(clippy::too_many_arguments)]
 pub(crate) async fn set_secret<R: Runtime>(
     app: AppHandle<R>,
+    window: Window<R>,
     username: String,
     secret: Vec<u8>,
+    service: Option<String>,
+    ttl: Option<u64>,
+    command_scope: CommandScope<ScopeEntry>,
+    global_scope: GlobalScope<ScopeEntry>,
 ) -> Result<()> {
-    app.keyring().set(
-        &username, 
-        CredentialType::Secret, 
-        CredentialValue::Secret(secret)
-    )
+    audit(&app, &window, "set_secret", service.as_deref(), Some(username.as_str()), || {
+        set_credential(
+            scoped_handle(&app, service.as_deref(), &username, &command_scope, &global_scope)?,
+            &username,
+            CredentialType::Secret,
+            CredentialValue::Secret(secret.into()),
+            ttl,
+        )
+    })
 }
 
 #[command]
 pub(crate) async fn get_password<R: Runtime>(
     app: AppHandle<R>,
+    window: Window<R>,
     username: String,
-) -> Result<String> {
-    match app.keyring().get(&username, CredentialType::Password)? {
-        CredentialValue::Password(password) => Ok(password),
-        _ => Err(crate::Error::InvalidInput("Expected password".into())),
-    }
+    service: Option<String>,
+    command_scope: CommandScope<ScopeEntry>,
+    global_scope: GlobalScope<ScopeEntry>,
+) -> Result<Zeroizing<String>> {
+    audit(&app, &window, "get_password", service.as_deref(), Some(username.as_str()), || {
+        match scoped_handle(&app, service.as_deref(), &username, &command_scope, &global_scope)?.get(&username, CredentialType::Password)? {
+            CredentialValue::Password(password) => Ok(password),
+            _ => Err(crate::Error::InvalidInput("Expected password".into())),
+        }
+    })
 }
 
 #[command]
 pub(crate) async fn get_secret<R: Runtime>(
     app: AppHandle<R>,
+    window: Window<R>,
     username: String,
-) -> Result<Vec<u8>> {
-    match app.keyring().get(&username, CredentialType::Secret)? {
-        CredentialValue::Secret(secret) => Ok(secret),
-        _ => Err(crate::Error::InvalidInput("Expected secret".into())),
-    }
+    service: Option<String>,
+    command_scope: CommandScope<ScopeEntry>,
+    global_scope: GlobalScope<ScopeEntry>,
+) -> Result<Zeroizing<Vec<u8>>> {
+    audit(&app, &window, "get_secret", service.as_deref(), Some(username.as_str()), || {
+        match scoped_handle(&app, service.as_deref(), &username, &command_scope, &global_scope)?.get(&username, CredentialType::Secret)? {
+            CredentialValue::Secret(secret) => Ok(secret),
+            _ => Err(crate::Error::InvalidInput("Expected secret".into())),
+        }
+    })
 }
 
 #[command]
 pub(crate) async fn delete_password<R: Runtime>(
     app: AppHandle<R>,
+    window: Window<R>,
     username: String,
+    service: Option<String>,
+    command_scope: CommandScope<ScopeEntry>,
+    global_scope: GlobalScope<ScopeEntry>,
 ) -> Result<()> {
-    app.keyring().delete(&username, CredentialType::Password)
+    audit(&app, &window, "delete_password", service.as_deref(), Some(username.as_str()), || {
+        scoped_handle(&app, service.as_deref(), &username, &command_scope, &global_scope)?.delete(&username, CredentialType::Password)
+    })
 }
 
 #[command]
 pub(crate) async fn delete_secret<R: Runtime>(
     app: AppHandle<R>,
+    window: Window<R>,
     username: String,
+    service: Option<String>,
+    command_scope: CommandScope<ScopeEntry>,
+    global_scope: GlobalScope<ScopeEntry>,
 ) -> Result<()> {
-    app.keyring().delete(&username, CredentialType::Secret)
+    audit(&app, &window, "delete_secret", service.as_deref(), Some(username.as_str()), || {
+        scoped_handle(&app, service.as_deref(), &username, &command_scope, &global_scope)?.delete(&username, CredentialType::Secret)
+    })
 }
 
 #[command]
 pub(crate) async fn has_password<R: Runtime>(
     app: AppHandle<R>,
+    window: Window<R>,
     username: String,
+    service: Option<String>,
+    command_scope: CommandScope<ScopeEntry>,
+    global_scope: GlobalScope<ScopeEntry>,
 ) -> Result<bool> {
-    app.keyring().exists(&username, CredentialType::Password)
+    audit(&app, &window, "has_password", service.as_deref(), Some(username.as_str()), || {
+        scoped_handle(&app, service.as_deref(), &username, &command_scope, &global_scope)?.exists(&username, CredentialType::Password)
+    })
 }
 
 #[command]
 pub(crate) async fn has_secret<R: Runtime>(
     app: AppHandle<R>,
+    window: Window<R>,
+    username: String,
+    service: Option<String>,
+    command_scope: CommandScope<ScopeEntry>,
+    global_scope: GlobalScope<ScopeEntry>,
+) -> Result<bool> {
+    audit(&app, &window, "has_secret", service.as_deref(), Some(username.as_str()), || {
+        scoped_handle(&app, service.as_deref(), &username, &command_scope, &global_scope)?.exists(&username, CredentialType::Secret)
+    })
+}
+
+#[command]
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn set_json<R: Runtime>(
+    app: AppHandle<R>,
+    window: Window<R>,
+    username: String,
+    value: serde_json::Value,
+    service: Option<String>,
+    ttl: Option<u64>,
+    command_scope: CommandScope<ScopeEntry>,
+    global_scope: GlobalScope<ScopeEntry>,
+) -> Result<()> {
+    audit(&app, &window, "set_json", service.as_deref(), Some(username.as_str()), || {
+        set_credential(
+            scoped_handle(&app, service.as_deref(), &username, &command_scope, &global_scope)?,
+            &username,
+            CredentialType::Json,
+            CredentialValue::Json(value),
+            ttl,
+        )
+    })
+}
+
+#[command]
+pub(crate) async fn get_json<R: Runtime>(
+    app: AppHandle<R>,
+    window: Window<R>,
+    username: String,
+    service: Option<String>,
+    command_scope: CommandScope<ScopeEntry>,
+    global_scope: GlobalScope<ScopeEntry>,
+) -> Result<serde_json::Value> {
+    audit(&app, &window, "get_json", service.as_deref(), Some(username.as_str()), || {
+        match scoped_handle(&app, service.as_deref(), &username, &command_scope, &global_scope)?.get(&username, CredentialType::Json)? {
+            CredentialValue::Json(value) => Ok(value),
+            _ => Err(crate::Error::InvalidInput("Expected JSON".into())),
+        }
+    })
+}
+
+#[command]
+pub(crate) async fn delete_json<R: Runtime>(
+    app: AppHandle<R>,
+    window: Window<R>,
+    username: String,
+    service: Option<String>,
+    command_scope: CommandScope<ScopeEntry>,
+    global_scope: GlobalScope<ScopeEntry>,
+) -> Result<()> {
+    audit(&app, &window, "delete_json", service.as_deref(), Some(username.as_str()), || {
+        scoped_handle(&app, service.as_deref(), &username, &command_scope, &global_scope)?.delete(&username, CredentialType::Json)
+    })
+}
+
+#[command]
+pub(crate) async fn has_json<R: Runtime>(
+    app: AppHandle<R>,
+    window: Window<R>,
     username: String,
+    service: Option<String>,
+    command_scope: CommandScope<ScopeEntry>,
+    global_scope: GlobalScope<ScopeEntry>,
 ) -> Result<bool> {
-    app.keyring().exists(&username, CredentialType::Secret)
+    audit(&app, &window, "has_json", service.as_deref(), Some(username.as_str()), || {
+        scoped_handle(&app, service.as_deref(), &username, &command_scope, &global_scope)?.exists(&username, CredentialType::Json)
+    })
+}
+
+#[command]
+pub(crate) async fn get_with_metadata<R: Runtime>(
+    app: AppHandle<R>,
+    window: Window<R>,
+    username: String,
+    credential_type: CredentialType,
+    service: Option<String>,
+    command_scope: CommandScope<ScopeEntry>,
+    global_scope: GlobalScope<ScopeEntry>,
+) -> Result<CredentialRecord> {
+    audit(&app, &window, "get_with_metadata", service.as_deref(), Some(username.as_str()), || {
+        scoped_handle(&app, service.as_deref(), &username, &command_scope, &global_scope)?.get_with_metadata(&username, credential_type)
+    })
+}
+
+#[command]
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn set_metadata<R: Runtime>(
+    app: AppHandle<R>,
+    window: Window<R>,
+    username: String,
+    credential_type: CredentialType,
+    label: Option<String>,
+    comment: Option<String>,
+    service: Option<String>,
+    command_scope: CommandScope<ScopeEntry>,
+    global_scope: GlobalScope<ScopeEntry>,
+) -> Result<()> {
+    audit(&app, &window, "set_metadata", service.as_deref(), Some(username.as_str()), || {
+        scoped_handle(&app, service.as_deref(), &username, &command_scope, &global_scope)?.set_metadata(&username, credential_type, label, comment)
+    })
+}
+
+#[command]
+pub(crate) async fn delete_user<R: Runtime>(
+    app: AppHandle<R>,
+    window: Window<R>,
+    username: String,
+    service: Option<String>,
+    command_scope: CommandScope<ScopeEntry>,
+    global_scope: GlobalScope<ScopeEntry>,
+) -> Result<()> {
+    audit(&app, &window, "delete_user", service.as_deref(), Some(username.as_str()), || {
+        scoped_handle(&app, service.as_deref(), &username, &command_scope, &global_scope)?.delete_user(&username)
+    })
+}
+
+#[command]
+pub(crate) async fn clear_service<R: Runtime>(
+    app: AppHandle<R>,
+    window: Window<R>,
+    service: Option<String>,
+    command_scope: CommandScope<ScopeEntry>,
+    global_scope: GlobalScope<ScopeEntry>,
+) -> Result<()> {
+    audit(&app, &window, "clear_service", service.as_deref(), None, || {
+        username_scope(&command_scope, &global_scope).check_unrestricted()?;
+        service_handle(&app, service.as_deref())?.clear_service()
+    })
+}
+
+#[command]
+pub(crate) async fn purge_expired<R: Runtime>(
+    app: AppHandle<R>,
+    window: Window<R>,
+    service: Option<String>,
+    command_scope: CommandScope<ScopeEntry>,
+    global_scope: GlobalScope<ScopeEntry>,
+) -> Result<usize> {
+    audit(&app, &window, "purge_expired", service.as_deref(), None, || {
+        username_scope(&command_scope, &global_scope).check_unrestricted()?;
+        service_handle(&app, service.as_deref())?.purge_expired()
+    })
+}
+
+#[command]
+pub(crate) async fn list_credentials<R: Runtime>(
+    app: AppHandle<R>,
+    window: Window<R>,
+    service: Option<String>,
+    command_scope: CommandScope<ScopeEntry>,
+    global_scope: GlobalScope<ScopeEntry>,
+) -> Result<Vec<(String, CredentialType)>> {
+    audit(&app, &window, "list_credentials", service.as_deref(), None, || {
+        // Only report usernames the caller could act on
+        let scope = username_scope(&command_scope, &global_scope);
+        let mut listed = service_handle(&app, service.as_deref())?.list()?;
+        listed.retain(|(username, _)| scope.is_allowed(username));
+        Ok(listed)
+    })
+}
+
+#[command]
+pub(crate) async fn generate_secret<R: Runtime>(
+    app: AppHandle<R>,
+    window: Window<R>,
+    username: String,
+    length: usize,
+    service: Option<String>,
+    command_scope: CommandScope<ScopeEntry>,
+    global_scope: GlobalScope<ScopeEntry>,
+) -> Result<()> {
+    audit(&app, &window, "generate_secret", service.as_deref(), Some(username.as_str()), || {
+        scoped_handle(&app, service.as_deref(), &username, &command_scope, &global_scope)?.generate_secret(&username, length)
+    })
+}
+
+#[command]
+pub(crate) async fn generate_password<R: Runtime>(
+    app: AppHandle<R>,
+    window: Window<R>,
+    username: String,
+    policy: Option<PasswordPolicy>,
+    service: Option<String>,
+    command_scope: CommandScope<ScopeEntry>,
+    global_scope: GlobalScope<ScopeEntry>,
+) -> Result<()> {
+    audit(&app, &window, "generate_password", service.as_deref(), Some(username.as_str()), || {
+        scoped_handle(&app, service.as_deref(), &username, &command_scope, &global_scope)?.generate_password(&username, &policy.unwrap_or_default())
+    })
+}
+
+#[command]
+pub(crate) async fn hmac_sha256<R: Runtime>(
+    app: AppHandle<R>,
+    window: Window<R>,
+    username: String,
+    data: Vec<u8>,
+    service: Option<String>,
+    command_scope: CommandScope<ScopeEntry>,
+    global_scope: GlobalScope<ScopeEntry>,
+) -> Result<Vec<u8>> {
+    audit(&app, &window, "hmac_sha256", service.as_deref(), Some(username.as_str()), || {
+        scoped_handle(&app, service.as_deref(), &username, &command_scope, &global_scope)?.secret_key(&username).hmac_sha256(&data)
+    })
+}
+
+#[command]
+pub(crate) async fn ed25519_sign<R: Runtime>(
+    app: AppHandle<R>,
+    window: Window<R>,
+    username: String,
+    message: Vec<u8>,
+    service: Option<String>,
+    command_scope: CommandScope<ScopeEntry>,
+    global_scope: GlobalScope<ScopeEntry>,
+) -> Result<Vec<u8>> {
+    audit(&app, &window, "ed25519_sign", service.as_deref(), Some(username.as_str()), || {
+        scoped_handle(&app, service.as_deref(), &username, &command_scope, &global_scope)?.secret_key(&username).ed25519_sign(&message)
+    })
+}
+
+#[command]
+pub(crate) async fn ed25519_public_key<R: Runtime>(
+    app: AppHandle<R>,
+    window: Window<R>,
+    username: String,
+    service: Option<String>,
+    command_scope: CommandScope<ScopeEntry>,
+    global_scope: GlobalScope<ScopeEntry>,
+) -> Result<Vec<u8>> {
+    audit(&app, &window, "ed25519_public_key", service.as_deref(), Some(username.as_str()), || {
+        scoped_handle(&app, service.as_deref(), &username, &command_scope, &global_scope)?.secret_key(&username).ed25519_public_key()
+    })
+}
+
+#[command]
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn aes_gcm_encrypt<R: Runtime>(
+    app: AppHandle<R>,
+    window: Window<R>,
+    username: String,
+    plaintext: Vec<u8>,
+    aad: Option<Vec<u8>>,
+    service: Option<String>,
+    command_scope: CommandScope<ScopeEntry>,
+    global_scope: GlobalScope<ScopeEntry>,
+) -> Result<Vec<u8>> {
+    audit(&app, &window, "aes_gcm_encrypt", service.as_deref(), Some(username.as_str()), || {
+        let plaintext = Zeroizing::new(plaintext);
+        scoped_handle(&app, service.as_deref(), &username, &command_scope, &global_scope)?
+            .secret_key(&username)
+            .aes_gcm_encrypt(&plaintext, &aad.unwrap_or_default())
+    })
+}
+
+#[command]
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn aes_gcm_decrypt<R: Runtime>(
+    app: AppHandle<R>,
+    window: Window<R>,
+    username: String,
+    ciphertext: Vec<u8>,
+    aad: Option<Vec<u8>>,
+    service: Option<String>,
+    command_scope: CommandScope<ScopeEntry>,
+    global_scope: GlobalScope<ScopeEntry>,
+) -> Result<Zeroizing<Vec<u8>>> {
+    audit(&app, &window, "aes_gcm_decrypt", service.as_deref(), Some(username.as_str()), || {
+        scoped_handle(&app, service.as_deref(), &username, &command_scope, &global_scope)?
+            .secret_key(&username)
+            .aes_gcm_decrypt(&ciphertext, &aad.unwrap_or_default())
+    })
+}
+
+#[command]
+pub(crate) async fn get_audit_events<R: Runtime>(
+    app: AppHandle<R>,
+    limit: Option<usize>,
+) -> Result<Vec<AuditEvent>> {
+    Ok(app.try_state::<AuditLog>().map(|log| log.recent(limit)).unwrap_or_default())
+}
+
+#[command]
+pub(crate) async fn migrate_naming<R: Runtime>(
+    app: AppHandle<R>,
+    window: Window<R>,
+    dry_run: Option<bool>,
+    service: Option<String>,
+    command_scope: CommandScope<ScopeEntry>,
+    global_scope: GlobalScope<ScopeEntry>,
+) -> Result<NamingMigration> {
+    audit(&app, &window, "migrate_naming", service.as_deref(), None, || {
+        username_scope(&command_scope, &global_scope).check_unrestricted()?;
+        service_handle(&app, service.as_deref())?.migrate_naming(dry_run.unwrap_or(false))
+    })
 }
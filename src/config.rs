@@ -0,0 +1,180 @@
+use std::path::PathBuf;
+
+use log::LevelFilter;
+use serde::Deserialize;
+use tauri::{AppHandle, Runtime};
+use zeroize::Zeroizing;
+
+use crate::chunks;
+use crate::implementation::KeyringOptions;
+use crate::redaction::RedactionKey;
+use crate::models::{AccessPolicy, LogRedaction, NamingScheme, SecretCompression, SecretEncoding, StoreBackend};
+
+/// Plugin configuration, read from `plugins.keyring` in `tauri.conf.json`.
+///
+/// ```json
+/// {
+///   "plugins": {
+///     "keyring": {
+///       "service": "com.example.myapp"
+///     }
+///   }
+/// }
+/// ```
+///
+/// Values set on [`Builder`](crate::Builder) take precedence over the config file.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Config {
+    /// Service name under which entries are stored. Defaults to the app identifier.
+    pub service: Option<String>,
+    /// Other services the frontend may pass as a command's `service` argument.
+    /// Defaults to none, so the frontend can only use the current service.
+    pub allowed_services: Option<Vec<String>>,
+    /// Credential store backend. Defaults to the platform's native store.
+    pub backend: Option<StoreBackend>,
+    /// Backends tried in order when `backend` cannot be opened (desktop only).
+    pub fallback_backends: Option<Vec<StoreBackend>>,
+    /// Keychain access policy for new entries (iOS only).
+    pub access_policy: Option<AccessPolicy>,
+    /// Encoding used when writing secrets. Defaults to raw.
+    pub secret_encoding: Option<SecretEncoding>,
+    /// Compression applied to secrets before encoding. Defaults to none;
+    /// `deflate` requires the `compression` feature.
+    pub secret_compression: Option<SecretCompression>,
+    /// How entries are named in the store. Defaults to the legacy layout.
+    pub naming_scheme: Option<NamingScheme>,
+    /// Values larger than this many bytes are split across several entries.
+    /// Defaults to 1024 for the Windows Credential Manager and 1 MiB, the most
+    /// allowed, elsewhere.
+    pub chunk_size: Option<usize>,
+    /// Settings for the `encryptedFile` backend.
+    pub encrypted_file: Option<EncryptedFileConfig>,
+    /// How usernames appear in log output. Defaults to hashed.
+    pub log_redaction: Option<LogRedaction>,
+    /// Record an audit event for every command. Disabled unless set.
+    pub audit: Option<AuditConfig>,
+}
+
+/// Settings for the audit log.
+///
+/// `"audit": {}` keeps recent events in memory only.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditConfig {
+    /// File to append events to as JSON Lines. Relative paths resolve against the app log dir.
+    pub path: Option<PathBuf>,
+}
+
+/// Settings for the encrypted file store (requires the `encrypted-file` feature).
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EncryptedFileConfig {
+    /// File to store entries in. Relative paths resolve against the app data dir.
+    /// Defaults to `keyring.bin`.
+    pub path: Option<PathBuf>,
+    /// Where the file encryption key comes from. Required.
+    pub key_source: Option<FileKeySource>,
+}
+
+/// Source of the encrypted file store's key
+#[derive(Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FileKeySource {
+    /// Derive the key from this passphrase. Prefer setting it from Rust over the config file.
+    Passphrase(Zeroizing<String>),
+    /// Derive the key from the passphrase in this environment variable.
+    PassphraseEnv(String),
+    /// Keep a random key in the Linux kernel keyring. The key, and therefore
+    /// the file contents, do not survive a reboot; the file is then moved aside
+    /// and an empty one started.
+    Keyutils,
+    /// Keep a random master key in the platform credential store, so the file
+    /// acts as a vault and the store holds a single item. Keyutils is never used
+    /// for it, since the key must survive a reboot.
+    Keyring,
+}
+
+// The passphrase must not end up in the Debug output of Config or Builder
+impl std::fmt::Debug for FileKeySource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FileKeySource::Passphrase(_) => f.write_str("Passphrase(<redacted>)"),
+            FileKeySource::PassphraseEnv(var) => f.debug_tuple("PassphraseEnv").field(var).finish(),
+            FileKeySource::Keyutils => f.write_str("Keyutils"),
+            FileKeySource::Keyring => f.write_str("Keyring"),
+        }
+    }
+}
+
+impl EncryptedFileConfig {
+    fn or(self, fallback: EncryptedFileConfig) -> EncryptedFileConfig {
+        EncryptedFileConfig {
+            path: self.path.or(fallback.path),
+            key_source: self.key_source.or(fallback.key_source),
+        }
+    }
+}
+
+impl AuditConfig {
+    fn or(self, fallback: AuditConfig) -> AuditConfig {
+        AuditConfig {
+            path: self.path.or(fallback.path),
+        }
+    }
+}
+
+impl Config {
+    /// The options every entry is handled with, once the backend is known
+    pub(crate) fn keyring_options(
+        &self,
+        log_level: LevelFilter,
+        backend: StoreBackend,
+        redaction_key: RedactionKey,
+    ) -> crate::Result<KeyringOptions> {
+        let options = KeyringOptions {
+            log_level,
+            access_policy: self.access_policy,
+            secret_encoding: self.secret_encoding.unwrap_or_default(),
+            secret_compression: self.secret_compression.unwrap_or_default(),
+            naming_scheme: self.naming_scheme.clone().unwrap_or_default(),
+            chunk_size: self.chunk_size.or_else(|| chunks::default_size(backend)),
+            log_redaction: self.log_redaction.unwrap_or_default(),
+            redaction_key,
+            backend,
+        };
+        options.validate()?;
+        Ok(options)
+    }
+
+    /// Resolve the service name, falling back to the app identifier
+    pub(crate) fn service_name<R: Runtime>(&self, app: &AppHandle<R>) -> String {
+        self.service
+            .clone()
+            .unwrap_or_else(|| app.config().identifier.clone())
+    }
+
+    /// Fill every unset value from `fallback`
+    pub(crate) fn or(self, fallback: Config) -> Config {
+        Config {
+            service: self.service.or(fallback.service),
+            allowed_services: self.allowed_services.or(fallback.allowed_services),
+            backend: self.backend.or(fallback.backend),
+            fallback_backends: self.fallback_backends.or(fallback.fallback_backends),
+            access_policy: self.access_policy.or(fallback.access_policy),
+            secret_encoding: self.secret_encoding.or(fallback.secret_encoding),
+            secret_compression: self.secret_compression.or(fallback.secret_compression),
+            naming_scheme: self.naming_scheme.or(fallback.naming_scheme),
+            chunk_size: self.chunk_size.or(fallback.chunk_size),
+            encrypted_file: match (self.encrypted_file, fallback.encrypted_file) {
+                (Some(ours), Some(theirs)) => Some(ours.or(theirs)),
+                (ours, theirs) => ours.or(theirs),
+            },
+            log_redaction: self.log_redaction.or(fallback.log_redaction),
+            audit: match (self.audit, fallback.audit) {
+                (Some(ours), Some(theirs)) => Some(ours.or(theirs)),
+                (ours, theirs) => ours.or(theirs),
+            },
+        }
+    }
+}
@@ -0,0 +1,169 @@
+//! Operations on stored secrets that run entirely in Rust.
+//!
+//! A [`SecretKey`] refers to a secret by username; the key bytes are read from
+//! the store for each operation and wiped afterwards, so callers (including the
+//! webview) only ever see the results.
+
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use ed25519_dalek::{Signer, SigningKey};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use zeroize::Zeroizing;
+
+use crate::implementation::KeyringImplementation;
+use crate::models::{CredentialType, CredentialValue};
+
+const AES_GCM_NONCE_LEN: usize = 12;
+
+/// A handle to a secret stored under a username, used as a key without exposing it
+pub struct SecretKey {
+    implementation: KeyringImplementation,
+    username: String,
+}
+
+impl SecretKey {
+    pub(crate) fn new(implementation: KeyringImplementation, username: &str) -> Self {
+        Self {
+            implementation,
+            username: username.to_string(),
+        }
+    }
+
+    /// The username the key is stored under
+    pub fn username(&self) -> &str {
+        &self.username
+    }
+
+    /// HMAC-SHA256 of `data`, keyed with the secret
+    pub fn hmac_sha256(&self, data: &[u8]) -> crate::Result<Vec<u8>> {
+        self.with_key(|key| hmac_sha256(key, data))
+    }
+
+    /// Ed25519 signature of `message`. The secret must be a 32-byte seed.
+    pub fn ed25519_sign(&self, message: &[u8]) -> crate::Result<Vec<u8>> {
+        self.with_key(|key| ed25519_sign(key, message))
+    }
+
+    /// The Ed25519 public key matching the secret seed
+    pub fn ed25519_public_key(&self) -> crate::Result<Vec<u8>> {
+        self.with_key(ed25519_public_key)
+    }
+
+    /// Encrypt with AES-256-GCM under the secret, which must be 32 bytes.
+    /// Returns the random nonce followed by the ciphertext.
+    pub fn aes_gcm_encrypt(&self, plaintext: &[u8], aad: &[u8]) -> crate::Result<Vec<u8>> {
+        self.with_key(|key| aes_gcm_encrypt(key, plaintext, aad))
+    }
+
+    /// Decrypt the output of [`aes_gcm_encrypt`](Self::aes_gcm_encrypt)
+    pub fn aes_gcm_decrypt(&self, ciphertext: &[u8], aad: &[u8]) -> crate::Result<Zeroizing<Vec<u8>>> {
+        self.with_key(|key| aes_gcm_decrypt(key, ciphertext, aad))
+    }
+
+    fn with_key<T>(&self, operation: impl FnOnce(&[u8]) -> crate::Result<T>) -> crate::Result<T> {
+        match self.implementation.get(&self.username, CredentialType::Secret)? {
+            CredentialValue::Secret(key) => operation(&key),
+            _ => Err(crate::Error::InvalidInput("Expected secret".into())),
+        }
+    }
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> crate::Result<Vec<u8>> {
+    let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(key)
+        .map_err(|e| crate::Error::CryptoError(e.to_string()))?;
+    mac.update(data);
+    Ok(mac.finalize().into_bytes().to_vec())
+}
+
+fn signing_key(key: &[u8]) -> crate::Result<SigningKey> {
+    let seed: &[u8; 32] = key
+        .try_into()
+        .map_err(|_| crate::Error::InvalidInput("Ed25519 keys must be 32 bytes".into()))?;
+    Ok(SigningKey::from_bytes(seed))
+}
+
+fn ed25519_sign(key: &[u8], message: &[u8]) -> crate::Result<Vec<u8>> {
+    Ok(signing_key(key)?.sign(message).to_bytes().to_vec())
+}
+
+fn ed25519_public_key(key: &[u8]) -> crate::Result<Vec<u8>> {
+    Ok(signing_key(key)?.verifying_key().to_bytes().to_vec())
+}
+
+fn aes_cipher(key: &[u8]) -> crate::Result<Aes256Gcm> {
+    if key.len() != 32 {
+        return Err(crate::Error::InvalidInput("AES-256-GCM keys must be 32 bytes".into()));
+    }
+    Ok(Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key)))
+}
+
+fn aes_gcm_encrypt(key: &[u8], plaintext: &[u8], aad: &[u8]) -> crate::Result<Vec<u8>> {
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let ciphertext = aes_cipher(key)?
+        .encrypt(&nonce, Payload { msg: plaintext, aad })
+        .map_err(|_| crate::Error::CryptoError("Encryption failed".into()))?;
+
+    let mut output = Vec::with_capacity(AES_GCM_NONCE_LEN + ciphertext.len());
+    output.extend_from_slice(&nonce);
+    output.extend_from_slice(&ciphertext);
+    Ok(output)
+}
+
+fn aes_gcm_decrypt(key: &[u8], ciphertext: &[u8], aad: &[u8]) -> crate::Result<Zeroizing<Vec<u8>>> {
+    if ciphertext.len() < AES_GCM_NONCE_LEN {
+        return Err(crate::Error::InvalidInput("Ciphertext is too short".into()));
+    }
+    let (nonce, ciphertext) = ciphertext.split_at(AES_GCM_NONCE_LEN);
+    aes_cipher(key)?
+        .decrypt(Nonce::from_slice(nonce), Payload { msg: ciphertext, aad })
+        .map(Zeroizing::new)
+        .map_err(|_| crate::Error::CryptoError("Decryption failed (wrong key or tampered data)".into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    fn unhex(s: &str) -> Vec<u8> {
+        (0..s.len()).step_by(2).map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap()).collect()
+    }
+
+    #[test]
+    fn test_hmac_sha256_rfc4231() {
+        let mac = hmac_sha256(b"Jefe", b"what do ya want for nothing?").unwrap();
+        assert_eq!(hex(&mac), "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843");
+    }
+
+    #[test]
+    fn test_ed25519_rfc8032() {
+        let seed = unhex("9d61b19deffd5a60ba844af492ec2cc44449c5697b326919703bac031cae7f60");
+        assert_eq!(
+            hex(&ed25519_public_key(&seed).unwrap()),
+            "d75a980182b10ab7d54bfed3c964073a0ee172f3daa62325af021a68f707511a"
+        );
+        assert_eq!(
+            hex(&ed25519_sign(&seed, b"").unwrap()),
+            "e5564300c360ac729086e2cc806e828a84877f1eb8e5d974d873e065224901555fb8821590a33bacc61e39701cf9b46bd25bf5f0595bbe24655141438e7a100b"
+        );
+        assert!(matches!(ed25519_sign(&seed[..16], b""), Err(crate::Error::InvalidInput(_))));
+    }
+
+    #[test]
+    fn test_aes_gcm_round_trip() {
+        let key = [42u8; 32];
+        let sealed = aes_gcm_encrypt(&key, b"attack at dawn", b"header").unwrap();
+        assert_eq!(sealed.len(), AES_GCM_NONCE_LEN + 14 + 16);
+        assert_eq!(aes_gcm_decrypt(&key, &sealed, b"header").unwrap().as_slice(), b"attack at dawn");
+
+        // Wrong associated data, wrong key and truncated input are all rejected
+        assert!(matches!(aes_gcm_decrypt(&key, &sealed, b"other"), Err(crate::Error::CryptoError(_))));
+        assert!(matches!(aes_gcm_decrypt(&[0u8; 32], &sealed, b"header"), Err(crate::Error::CryptoError(_))));
+        assert!(aes_gcm_decrypt(&key, &sealed[..4], b"header").is_err());
+        assert!(matches!(aes_gcm_encrypt(&key[..16], b"", b""), Err(crate::Error::InvalidInput(_))));
+    }
+}
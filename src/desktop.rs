@@ -1,82 +1,285 @@
-use serde::de::DeserializeOwned;
-use tauri::{plugin::PluginApi, AppHandle, Runtime};
+use std::sync::Arc;
 
-use crate::implementation::KeyringImplementation;
+use keyring_core::CredentialStore;
+use log::LevelFilter;
+use tauri::{plugin::PluginApi, AppHandle, Manager, Runtime};
+#[cfg(feature = "encrypted-file")]
+use zeroize::Zeroizing;
+
+use crate::config::Config;
+#[cfg(feature = "encrypted-file")]
+use crate::config::FileKeySource;
+#[cfg(feature = "encrypted-file")]
+use crate::implementation::{KeyringImplementation, KeyringOptions};
+use crate::keyring::Keyring;
 use crate::models::*;
+use crate::redaction::RedactionKey;
+
+/// Username the vault's master key is stored under in the platform store
+#[cfg(feature = "encrypted-file")]
+const VAULT_KEY_USERNAME: &str = ".vault-key";
 
-pub fn init<R: Runtime, C: DeserializeOwned>(
+pub fn init<R: Runtime>(
     app: &AppHandle<R>,
-    _api: PluginApi<R, C>,
+    _api: PluginApi<R, Option<Config>>,
+    config: Config,
+    log_level: LevelFilter,
 ) -> crate::Result<Keyring<R>> {
-    if std::env::var("KEYRING_USE_MOCK").is_ok() {
-        use keyring_core::mock::Store;
-        let store = Store::new().map_err(|e| crate::Error::PlatformError(e.to_string()))?;
-        keyring_core::set_default_store(store);
-        return Ok(Keyring(app.clone()));
+    let service = config.service_name(app);
+    // KEYRING_USE_MOCK is still honoured so existing test setups keep working,
+    // but never overrides a backend the app configured explicitly
+    let chain = if config.backend.is_none() && std::env::var("KEYRING_USE_MOCK").is_ok() {
+        keyring_log!(level = log_level, Warn, "KEYRING_USE_MOCK is set; credentials are kept in memory only");
+        vec![StoreBackend::Mock]
+    } else {
+        backend_chain(&config)
+    };
+
+    let (backend, store) = open_first_available(app, &config, &chain, log_level)?;
+    keyring_log!(level = log_level, Info, "Using {:?} credential store", backend);
+    keyring_core::set_default_store(store);
+
+    let options = config.keyring_options(log_level, backend, app.state::<RedactionKey>().inner().clone())?;
+    Ok(Keyring::new(app.clone(), service, config.allowed_services.clone().unwrap_or_default(), options))
+}
+
+/// The ordered list of backends to try: the configured one, then its fallbacks
+fn backend_chain(config: &Config) -> Vec<StoreBackend> {
+    let mut chain = Vec::new();
+    let requested = std::iter::once(config.backend.unwrap_or_default())
+        .chain(config.fallback_backends.iter().flatten().copied());
+    for backend in requested {
+        // Native expands to the stores compiled in for this platform
+        let expanded = match backend {
+            StoreBackend::Native => native_backends(),
+            other => vec![other],
+        };
+        for backend in expanded {
+            if !chain.contains(&backend) {
+                chain.push(backend);
+            }
+        }
     }
+    chain
+}
 
-    // Initialize platform-specific store
-    #[cfg(target_os = "windows")]
-    {
-        use windows_native_keyring_store::Store as WindowsStore;
-        let store = WindowsStore::new().map_err(|e| crate::Error::PlatformError(e.to_string()))?;
-        keyring_core::set_default_store(store);
+#[cfg(target_os = "linux")]
+fn native_backends() -> Vec<StoreBackend> {
+    let mut backends = Vec::new();
+    if cfg!(feature = "dbus-secret-service") {
+        backends.push(StoreBackend::SecretService);
+    }
+    if cfg!(feature = "linux-keyutils") {
+        backends.push(StoreBackend::Keyutils);
     }
+    backends
+}
+
+#[cfg(not(target_os = "linux"))]
+fn native_backends() -> Vec<StoreBackend> {
+    vec![StoreBackend::Native]
+}
 
-    #[cfg(target_os = "macos")]
-    {
-        use apple_native_keyring_store::keychain::Store as MacOSStore;
-        let store = MacOSStore::new().map_err(|e| crate::Error::PlatformError(e.to_string()))?;
-        keyring_core::set_default_store(store);
+/// Open each backend in turn, returning the first that succeeds
+fn open_first_available<R: Runtime>(
+    app: &AppHandle<R>,
+    config: &Config,
+    chain: &[StoreBackend],
+    log_level: LevelFilter,
+) -> crate::Result<(StoreBackend, Arc<CredentialStore>)> {
+    let mut last_error = crate::Error::PlatformError("No credential store backend configured".into());
+    for &backend in chain {
+        match open_store(app, config, backend, log_level) {
+            Ok(store) => return Ok((backend, store)),
+            Err(e) => {
+                keyring_log!(level = log_level, Warn, "{:?} credential store unavailable: {}", backend, e);
+                last_error = e;
+            }
+        }
     }
+    Err(last_error)
+}
 
-    #[cfg(target_os = "linux")]
-    {
-        #[cfg(feature = "dbus-secret-service")]
-        use dbus_secret_service_keyring_store::Store as LinuxStore;
-        #[cfg(feature = "linux-keyutils")]
-        use linux_keyutils_keyring_store::Store as LinuxStore;
-        let store = LinuxStore::new().map_err(|e| crate::Error::PlatformError(e.to_string()))?;
-        keyring_core::set_default_store(store);
+fn open_store<R: Runtime>(
+    app: &AppHandle<R>,
+    config: &Config,
+    backend: StoreBackend,
+    log_level: LevelFilter,
+) -> crate::Result<Arc<CredentialStore>> {
+    match backend {
+        StoreBackend::Mock => {
+            use keyring_core::mock::Store;
+            let store = Store::new().map_err(|e| crate::Error::PlatformError(e.to_string()))?;
+            Ok(store)
+        }
+        StoreBackend::Native => open_native_store(),
+        StoreBackend::SecretService => open_secret_service_store(),
+        StoreBackend::Keyutils => open_keyutils_store(),
+        StoreBackend::EncryptedFile => {
+            open_encrypted_file_store(app, config, log_level)
+        }
     }
+}
 
-    Ok(Keyring(app.clone()))
+#[cfg(target_os = "windows")]
+fn open_native_store() -> crate::Result<Arc<CredentialStore>> {
+    use windows_native_keyring_store::Store as WindowsStore;
+    let store = WindowsStore::new().map_err(|e| crate::Error::PlatformError(e.to_string()))?;
+    Ok(store)
 }
 
-/// Access to the keyring APIs.
-pub struct Keyring<R: Runtime>(AppHandle<R>);
+#[cfg(target_os = "macos")]
+fn open_native_store() -> crate::Result<Arc<CredentialStore>> {
+    use apple_native_keyring_store::keychain::Store as MacOSStore;
+    let store = MacOSStore::new().map_err(|e| crate::Error::PlatformError(e.to_string()))?;
+    Ok(store)
+}
 
-impl<R: Runtime> Keyring<R> {
-    fn implementation(&self) -> KeyringImplementation {
-        KeyringImplementation
-    }
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+fn open_native_store() -> crate::Result<Arc<CredentialStore>> {
+    Err(unavailable(StoreBackend::Native))
+}
 
-    pub fn initialize_service(&self, service_name: String) -> crate::Result<()> {
-        KeyringImplementation::initialize_service(service_name)
-    }
+#[cfg(all(target_os = "linux", feature = "dbus-secret-service"))]
+fn open_secret_service_store() -> crate::Result<Arc<CredentialStore>> {
+    use dbus_secret_service_keyring_store::Store as SecretServiceStore;
+    let store = SecretServiceStore::new().map_err(|e| crate::Error::PlatformError(e.to_string()))?;
+    Ok(store)
+}
 
-    pub fn set(
-        &self,
-        username: &str,
-        credential_type: CredentialType,
-        value: CredentialValue,
-    ) -> crate::Result<()> {
-        self.implementation().set(username, credential_type, value)
-    }
+#[cfg(not(all(target_os = "linux", feature = "dbus-secret-service")))]
+fn open_secret_service_store() -> crate::Result<Arc<CredentialStore>> {
+    Err(unavailable(StoreBackend::SecretService))
+}
+
+#[cfg(all(target_os = "linux", feature = "linux-keyutils"))]
+fn open_keyutils_store() -> crate::Result<Arc<CredentialStore>> {
+    use linux_keyutils_keyring_store::Store as KeyutilsStore;
+    let store = KeyutilsStore::new().map_err(|e| crate::Error::PlatformError(e.to_string()))?;
+    Ok(store)
+}
+
+#[cfg(not(all(target_os = "linux", feature = "linux-keyutils")))]
+fn open_keyutils_store() -> crate::Result<Arc<CredentialStore>> {
+    Err(unavailable(StoreBackend::Keyutils))
+}
+
+#[cfg(feature = "encrypted-file")]
+fn open_encrypted_file_store<R: Runtime>(
+    app: &AppHandle<R>,
+    plugin_config: &Config,
+    log_level: LevelFilter,
+) -> crate::Result<Arc<CredentialStore>> {
+    use crate::file_store::{FileKey, Store as FileStore};
+
+    let config = plugin_config.encrypted_file.clone().unwrap_or_default();
+    let relative = config.path.clone().unwrap_or_else(|| "keyring.bin".into());
+    let path = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| crate::Error::PlatformError(e.to_string()))?
+        .join(relative);
+
+    let key = match config.key_source.clone() {
+        Some(FileKeySource::Passphrase(passphrase)) => FileKey::Passphrase(passphrase),
+        Some(FileKeySource::PassphraseEnv(var)) => {
+            let passphrase = std::env::var(&var)
+                .map_err(|_| crate::Error::InvalidInput(format!("{} is not set", var)))?;
+            FileKey::Passphrase(passphrase.into())
+        }
+        Some(FileKeySource::Keyutils) => FileKey::Session(keyutils_file_key(&path)?),
+        Some(FileKeySource::Keyring) => FileKey::Raw(keyring_file_key(app, plugin_config, &path, log_level)?),
+        None => {
+            return Err(crate::Error::InvalidInput(
+                "The encrypted file backend requires a key source".into(),
+            ))
+        }
+    };
 
-    pub fn get(
-        &self,
-        username: &str,
-        credential_type: CredentialType,
-    ) -> crate::Result<CredentialValue> {
-        self.implementation().get(username, credential_type)
+    let store = FileStore::new(&path, key)?;
+    if let Some(aside) = store.set_aside() {
+        keyring_log!(
+            level = log_level,
+            Warn,
+            "{} could not be decrypted with this session's key; moved it to {} and started an empty file",
+            path.display(),
+            aside.display()
+        );
     }
+    Ok(store)
+}
+
+#[cfg(not(feature = "encrypted-file"))]
+fn open_encrypted_file_store<R: Runtime>(
+    _app: &AppHandle<R>,
+    _config: &Config,
+    _log_level: LevelFilter,
+) -> crate::Result<Arc<CredentialStore>> {
+    Err(unavailable(StoreBackend::EncryptedFile))
+}
+
+#[cfg(all(feature = "encrypted-file", target_os = "linux", feature = "linux-keyutils"))]
+fn keyutils_file_key(path: &std::path::Path) -> crate::Result<Zeroizing<[u8; 32]>> {
+    crate::file_store::keyutils_key(&path.display().to_string()).map_err(Into::into)
+}
 
-    pub fn delete(&self, username: &str, credential_type: CredentialType) -> crate::Result<()> {
-        self.implementation().delete(username, credential_type)
+#[cfg(all(feature = "encrypted-file", not(all(target_os = "linux", feature = "linux-keyutils"))))]
+fn keyutils_file_key(_path: &std::path::Path) -> crate::Result<Zeroizing<[u8; 32]>> {
+    Err(crate::Error::InvalidInput(
+        "The keyutils key source requires Linux and the linux-keyutils feature".into(),
+    ))
+}
+
+/// Fetch the vault's master key from the platform store, creating it when the
+/// vault file does not exist yet.
+///
+/// The platform store stays the default only until the file store replaces it,
+/// so the key is the one item the plugin keeps there. It is always stored with
+/// the default options, so changing the naming scheme, chunk size or encoding
+/// later does not lose it.
+#[cfg(feature = "encrypted-file")]
+fn keyring_file_key<R: Runtime>(
+    app: &AppHandle<R>,
+    config: &Config,
+    path: &std::path::Path,
+    log_level: LevelFilter,
+) -> crate::Result<Zeroizing<[u8; 32]>> {
+    // Keyutils forgets the key on reboot, which would leave the file undecryptable
+    let persistent: Vec<StoreBackend> = native_backends()
+        .into_iter()
+        .filter(|backend| *backend != StoreBackend::Keyutils)
+        .collect();
+    if persistent.is_empty() {
+        return Err(crate::Error::PlatformError(
+            "The keyring key source needs a persistent platform store, and none is available in this build".into(),
+        ));
     }
+    let (backend, store) = open_first_available(app, config, &persistent, log_level)?;
+    keyring_core::set_default_store(store);
+    let options = KeyringOptions {
+        log_level,
+        redaction_key: app.state::<RedactionKey>().inner().clone(),
+        backend,
+        ..KeyringOptions::default()
+    };
+    let vault = KeyringImplementation::new(config.service_name(app), options);
 
-    pub fn exists(&self, username: &str, credential_type: CredentialType) -> crate::Result<bool> {
-        self.implementation().exists(username, credential_type)
+    // A new key could never decrypt an existing file
+    let key = vault.key(VAULT_KEY_USERNAME, 32, !path.exists()).map_err(|e| match e {
+        crate::Error::EntryNotFound => crate::Error::PlatformError(format!(
+            "The vault key is missing from the platform store, so {} cannot be decrypted",
+            path.display()
+        )),
+        e => e,
+    })?;
+    if key.len() != 32 {
+        return Err(crate::Error::InvalidInput("The vault key has the wrong length".into()));
     }
+    let mut bytes = Zeroizing::new([0u8; 32]);
+    bytes.copy_from_slice(&key);
+    Ok(bytes)
+}
+
+fn unavailable(backend: StoreBackend) -> crate::Error {
+    crate::Error::PlatformError(format!("{:?} credential store is not available in this build", backend))
 }
@@ -0,0 +1,278 @@
+//! The format secrets are written to the store in.
+//!
+//! Every secret starts with a short ASCII header naming the format version and
+//! how the payload is encoded: `KRS1r:` is followed by the raw bytes and
+//! `KRS1b:` by base64. Entries written before the header existed hold bare
+//! base64, which can never contain the `:` that ends a header; anything else
+//! without a header is reported as invalid rather than returned as-is.
+//!
+//! An uppercase tag (`KRS1R:`, `KRS1B:`) marks a payload that was deflated
+//! before being encoded. Secrets are only stored compressed when that makes
+//! them smaller, and reading one back requires the `compression` feature.
+
+use base64::{Engine as _, engine::general_purpose::STANDARD as BASE64};
+use zeroize::Zeroizing;
+
+use crate::models::{SecretCompression, SecretEncoding};
+
+const MAGIC: &[u8] = b"KRS";
+const VERSION: u8 = b'1';
+const HEADER_LEN: usize = MAGIC.len() + 3;
+
+/// Compressed secrets that would inflate beyond this are rejected as corrupt
+#[cfg(feature = "compression")]
+const MAX_DECOMPRESSED_LEN: usize = 16 * 1024 * 1024;
+
+/// How a stored secret was written
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum StoredFormat {
+    /// Behind a current header, with the given payload encoding and compression
+    Versioned(SecretEncoding, SecretCompression),
+    /// Without a header, from before the format was versioned
+    Legacy,
+}
+
+fn header(encoding: SecretEncoding, compression: SecretCompression) -> [u8; HEADER_LEN] {
+    let tag = match encoding {
+        SecretEncoding::Raw => b'r',
+        SecretEncoding::Base64 => b'b',
+    };
+    let tag = match compression {
+        SecretCompression::None => tag,
+        SecretCompression::Deflate => tag.to_ascii_uppercase(),
+    };
+    [MAGIC[0], MAGIC[1], MAGIC[2], VERSION, tag, b':']
+}
+
+/// Secret bytes as written to the store
+pub(crate) fn encode(secret: &[u8], encoding: SecretEncoding, compression: SecretCompression) -> Zeroizing<Vec<u8>> {
+    let compressed = match compression {
+        SecretCompression::Deflate => compress(secret),
+        SecretCompression::None => None,
+    };
+    match compressed {
+        Some(compressed) => encode_payload(&compressed, encoding, SecretCompression::Deflate),
+        None => encode_payload(secret, encoding, SecretCompression::None),
+    }
+}
+
+fn encode_payload(payload: &[u8], encoding: SecretEncoding, compression: SecretCompression) -> Zeroizing<Vec<u8>> {
+    let header = header(encoding, compression);
+    match encoding {
+        SecretEncoding::Raw => {
+            let mut stored = Zeroizing::new(Vec::with_capacity(HEADER_LEN + payload.len()));
+            stored.extend_from_slice(&header);
+            stored.extend_from_slice(payload);
+            stored
+        },
+        SecretEncoding::Base64 => {
+            let encoded_len = base64::encoded_len(payload.len(), true).unwrap_or_default();
+            let mut stored = Zeroizing::new(vec![0; HEADER_LEN + encoded_len]);
+            stored[..HEADER_LEN].copy_from_slice(&header);
+            let written = BASE64.encode_slice(payload, &mut stored[HEADER_LEN..]).unwrap_or_default();
+            stored.truncate(HEADER_LEN + written);
+            stored
+        },
+    }
+}
+
+/// The format a stored secret was written in
+pub(crate) fn format_of(stored: &[u8]) -> crate::Result<StoredFormat> {
+    if !stored.starts_with(MAGIC) || stored.get(HEADER_LEN - 1) != Some(&b':') {
+        return Ok(StoredFormat::Legacy);
+    }
+    let (version, tag) = (stored[MAGIC.len()], stored[MAGIC.len() + 1]);
+    let encoding = match tag.to_ascii_lowercase() {
+        b'r' => Some(SecretEncoding::Raw),
+        b'b' => Some(SecretEncoding::Base64),
+        _ => None,
+    };
+    let compression = if tag.is_ascii_uppercase() {
+        SecretCompression::Deflate
+    } else {
+        SecretCompression::None
+    };
+    match (version, encoding) {
+        (VERSION, Some(encoding)) => Ok(StoredFormat::Versioned(encoding, compression)),
+        _ => Err(crate::Error::InvalidInput(format!(
+            "Unsupported secret format {}{}",
+            version as char, tag as char
+        ))),
+    }
+}
+
+/// Secret bytes as read back from the store, in any supported format
+pub(crate) fn decode(mut stored: Zeroizing<Vec<u8>>) -> crate::Result<Zeroizing<Vec<u8>>> {
+    match format_of(&stored)? {
+        StoredFormat::Versioned(SecretEncoding::Raw, SecretCompression::None) => {
+            // Shift the payload down in place so no unwiped copy is made
+            stored.drain(..HEADER_LEN);
+            Ok(stored)
+        },
+        StoredFormat::Versioned(SecretEncoding::Base64, SecretCompression::None) => decode_base64(&stored[HEADER_LEN..]),
+        StoredFormat::Versioned(SecretEncoding::Raw, SecretCompression::Deflate) => decompress(&stored[HEADER_LEN..]),
+        StoredFormat::Versioned(SecretEncoding::Base64, SecretCompression::Deflate) => {
+            decompress(&decode_base64(&stored[HEADER_LEN..])?)
+        },
+        // Secrets were always base64 before the header existed
+        StoredFormat::Legacy => decode_base64(&stored)
+            .map_err(|_| crate::Error::InvalidInput("Invalid base64 data".into())),
+    }
+}
+
+fn decode_base64(encoded: &[u8]) -> crate::Result<Zeroizing<Vec<u8>>> {
+    let encoded = std::str::from_utf8(encoded).map_err(|_| crate::Error::InvalidUtf8)?;
+    // Decode into a buffer sized up front so it is never reallocated
+    let mut secret = Zeroizing::new(Vec::with_capacity(base64::decoded_len_estimate(encoded.len())));
+    BASE64.decode_vec(encoded, &mut secret)
+        .map_err(|_| crate::Error::InvalidInput("Invalid base64 data".into()))?;
+    Ok(secret)
+}
+
+/// Deflate a secret, or `None` when that would not make it smaller
+#[cfg(feature = "compression")]
+fn compress(secret: &[u8]) -> Option<Zeroizing<Vec<u8>>> {
+    use flate2::{Compress, Compression, FlushCompress, Status};
+
+    // Output that does not fit in the input's size is not worth keeping, so
+    // the buffer never has to grow and leave an unwiped copy behind
+    let mut compressed = Zeroizing::new(Vec::with_capacity(secret.len()));
+    let mut deflate = Compress::new(Compression::best(), false);
+    match deflate.compress_vec(secret, &mut compressed, FlushCompress::Finish) {
+        Ok(Status::StreamEnd) if compressed.len() < secret.len() => Some(compressed),
+        _ => None,
+    }
+}
+
+#[cfg(not(feature = "compression"))]
+fn compress(_secret: &[u8]) -> Option<Zeroizing<Vec<u8>>> {
+    None
+}
+
+#[cfg(feature = "compression")]
+fn decompress(compressed: &[u8]) -> crate::Result<Zeroizing<Vec<u8>>> {
+    use flate2::{Decompress, FlushDecompress, Status};
+
+    let corrupt = || crate::Error::InvalidInput("Invalid compressed data".into());
+    let mut inflate = Decompress::new(false);
+    let mut secret = Zeroizing::new(Vec::with_capacity((compressed.len() * 4).max(64)));
+    loop {
+        let consumed = inflate.total_in() as usize;
+        let status = inflate
+            .decompress_vec(&compressed[consumed..], &mut secret, FlushDecompress::None)
+            .map_err(|_| corrupt())?;
+        match status {
+            Status::StreamEnd => return Ok(secret),
+            _ if secret.len() == secret.capacity() && secret.capacity() < MAX_DECOMPRESSED_LEN => {
+                // Grow into a new buffer so the old one is wiped when dropped
+                let mut larger = Zeroizing::new(Vec::with_capacity(secret.capacity() * 2));
+                larger.extend_from_slice(&secret);
+                secret = larger;
+            },
+            _ => return Err(corrupt()),
+        }
+    }
+}
+
+#[cfg(not(feature = "compression"))]
+fn decompress(_compressed: &[u8]) -> crate::Result<Zeroizing<Vec<u8>>> {
+    Err(crate::Error::InvalidInput("Reading a compressed secret requires the `compression` feature".into()))
+}
+
+/// `value` serialized as JSON into a buffer allocated once at its final size,
+/// so growing it never leaves an unwiped partial copy behind
+pub(crate) fn to_json(value: &impl serde::Serialize) -> serde_json::Result<Zeroizing<Vec<u8>>> {
+    /// Counts the bytes written to it without keeping them
+    struct Counter(usize);
+
+    impl std::io::Write for Counter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0 += buf.len();
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let mut counter = Counter(0);
+    serde_json::to_writer(&mut counter, value)?;
+    let mut json = Zeroizing::new(Vec::with_capacity(counter.0));
+    serde_json::to_writer(&mut *json, value)?;
+    Ok(json)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let secret = [0x00, 0xFF, 0x3A, 0x80];
+        for encoding in [SecretEncoding::Raw, SecretEncoding::Base64] {
+            let stored = encode(&secret, encoding, SecretCompression::None);
+            assert_eq!(format_of(&stored).unwrap(), StoredFormat::Versioned(encoding, SecretCompression::None));
+            assert_eq!(&decode(stored).unwrap()[..], &secret);
+        }
+
+        assert_eq!(&encode(&secret, SecretEncoding::Raw, SecretCompression::None)[..], b"KRS1r:\x00\xff\x3a\x80");
+        assert_eq!(&encode(&secret, SecretEncoding::Base64, SecretCompression::None)[..], b"KRS1b:AP86gA==");
+        assert_eq!(&encode(&[], SecretEncoding::Base64, SecretCompression::None)[..], b"KRS1b:");
+    }
+
+    #[test]
+    fn test_legacy_base64() {
+        let stored = Zeroizing::new(b"dG9wIHNlY3JldA==".to_vec());
+        assert_eq!(format_of(&stored).unwrap(), StoredFormat::Legacy);
+        assert_eq!(&decode(stored).unwrap()[..], b"top secret");
+
+        // Base64 can start with the magic bytes but never contains the header's `:`
+        let lookalike = Zeroizing::new(BASE64.encode(b")\x14\xb5 header").into_bytes());
+        assert!(lookalike.starts_with(MAGIC));
+        assert_eq!(&decode(lookalike).unwrap()[..], b")\x14\xb5 header");
+
+    }
+
+    #[test]
+    fn test_corrupt_legacy_data_is_rejected() {
+        for corrupt in [&[0x00, 0xFF, 0x80, 0x7F][..], b"not base64!", b"dG9wIHNlY3JldA="] {
+            let stored = Zeroizing::new(corrupt.to_vec());
+            assert_eq!(format_of(&stored).unwrap(), StoredFormat::Legacy);
+            assert!(matches!(decode(stored), Err(crate::Error::InvalidInput(_))));
+        }
+    }
+
+    #[test]
+    fn test_unknown_version() {
+        assert!(matches!(format_of(b"KRS9r:data"), Err(crate::Error::InvalidInput(_))));
+        assert!(matches!(decode(Zeroizing::new(b"KRS1x:data".to_vec())), Err(crate::Error::InvalidInput(_))));
+    }
+
+    #[cfg(feature = "compression")]
+    #[test]
+    fn test_compression() {
+        let document = br#"{"certificates":["MIIB","MIIB","MIIB","MIIB"],"chain":"MIIB MIIB MIIB MIIB MIIB"}"#.repeat(20);
+        for (encoding, header) in [(SecretEncoding::Raw, b"KRS1R:"), (SecretEncoding::Base64, b"KRS1B:")] {
+            let stored = encode(&document, encoding, SecretCompression::Deflate);
+            assert!(stored.starts_with(header));
+            assert!(stored.len() < document.len() / 4);
+            assert_eq!(format_of(&stored).unwrap(), StoredFormat::Versioned(encoding, SecretCompression::Deflate));
+            assert_eq!(&decode(stored).unwrap()[..], &document[..]);
+        }
+
+        // Secrets that deflate cannot shrink are stored as they are
+        let incompressible = [0x00, 0xFF, 0x3A, 0x80];
+        assert_eq!(&encode(&incompressible, SecretEncoding::Raw, SecretCompression::Deflate)[..], b"KRS1r:\x00\xff\x3a\x80");
+
+        assert!(matches!(decode(Zeroizing::new(b"KRS1R:not deflate".to_vec())), Err(crate::Error::InvalidInput(_))));
+    }
+
+    #[cfg(not(feature = "compression"))]
+    #[test]
+    fn test_compression_unavailable() {
+        let stored = encode(b"aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa", SecretEncoding::Raw, SecretCompression::Deflate);
+        assert!(stored.starts_with(b"KRS1r:"));
+        assert!(matches!(decode(Zeroizing::new(b"KRS1R:data".to_vec())), Err(crate::Error::InvalidInput(_))));
+    }
+}
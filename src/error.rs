@@ -23,6 +23,18 @@ pub enum Error {
     #[error("Invalid UTF-8 data")]
     InvalidUtf8,
     
+    #[error("Invalid JSON data: {0}")]
+    InvalidJson(String),
+    
+    #[error("Username is outside the scope granted to this window")]
+    OutOfScope,
+    
+    #[error("Service is not allowed for the frontend: {0}")]
+    ServiceNotAllowed(String),
+    
+    #[error("Cryptographic operation failed: {0}")]
+    CryptoError(String),
+    
     #[error("IO error: {0}")]
     Io(String),
     
@@ -45,6 +57,12 @@ impl From<keyring_core::Error> for Error {
     }
 }
 
+impl From<serde_json::Error> for Error {
+    fn from(err: serde_json::Error) -> Self {
+        Error::InvalidJson(err.to_string())
+    }
+}
+
 impl From<std::io::Error> for Error {
     fn from(err: std::io::Error) -> Self {
         Error::Io(err.to_string())
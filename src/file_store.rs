@@ -0,0 +1,459 @@
+//! Credential store that keeps every entry in a single AES-256-GCM encrypted file.
+//!
+//! File layout: `TKRF` magic, format version, 16 byte salt, 12 byte nonce, ciphertext.
+//! The plaintext is a JSON list of `{ service, user, secret }` records.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use aes_gcm::aead::rand_core::RngCore;
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use base64::{Engine as _, engine::general_purpose::STANDARD as BASE64};
+use keyring_core::api::{CredentialApi, CredentialStoreApi};
+use keyring_core::{Credential, Entry, Error, Result};
+use serde::{Deserialize, Serialize};
+use zeroize::Zeroizing;
+
+const MAGIC: &[u8; 4] = b"TKRF";
+const VERSION: u8 = 1;
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+const HEADER_LEN: usize = MAGIC.len() + 1 + SALT_LEN + NONCE_LEN;
+
+/// Where the file encryption key comes from
+pub enum FileKey {
+    /// Derive the key from a passphrase with Argon2id, salted per file
+    Passphrase(Zeroizing<String>),
+    /// Use a 256-bit key as-is
+    Raw(Zeroizing<[u8; 32]>),
+    /// Use a 256-bit key that does not outlive the login session. A file that
+    /// no longer decrypts under it was written in an earlier session: it is
+    /// renamed to `<file>.unreadable-<unix seconds>` and an empty one started.
+    Session(Zeroizing<[u8; 32]>),
+}
+
+/// Fetch the file key held in the kernel keyring under `id`, creating it on first use.
+///
+/// Keyutils does not persist across reboots, so use it as a [`FileKey::Session`]:
+/// the file's contents are lost once the machine restarts.
+#[cfg(all(target_os = "linux", feature = "linux-keyutils"))]
+pub fn keyutils_key(id: &str) -> Result<Zeroizing<[u8; 32]>> {
+    let store = linux_keyutils_keyring_store::Store::new()?;
+    let entry = store.build("tauri-plugin-keyring", id, None)?;
+    let mut key = Zeroizing::new([0u8; 32]);
+    match entry.get_secret().map(Zeroizing::new) {
+        Ok(bytes) if bytes.len() == key.len() => key.copy_from_slice(&bytes),
+        Ok(_) => {
+            return Err(Error::Invalid("key".to_string(), "stored file key has the wrong length".to_string()))
+        }
+        Err(Error::NoEntry) => {
+            OsRng.fill_bytes(&mut key[..]);
+            entry.set_secret(&key[..])?;
+        }
+        Err(e) => return Err(e),
+    }
+    Ok(key)
+}
+
+/// A single persisted credential
+#[derive(Serialize, Deserialize)]
+struct Record {
+    service: String,
+    user: String,
+    secret: Zeroizing<String>,
+}
+
+/// Decrypted secrets by service and user, wiped when dropped or replaced
+type Entries = HashMap<(String, String), Zeroizing<Vec<u8>>>;
+
+/// The decrypted file contents and what is needed to write them back
+struct Vault {
+    path: PathBuf,
+    salt: [u8; SALT_LEN],
+    cipher: Aes256Gcm,
+    entries: Mutex<Entries>,
+    /// Where an earlier session's file was moved, if it could not be decrypted
+    set_aside: Option<PathBuf>,
+}
+
+impl Vault {
+    fn open(path: &Path, key: FileKey) -> Result<Self> {
+        let existing = match std::fs::read(path) {
+            Ok(bytes) => Some(bytes),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+            Err(e) => return Err(Error::NoStorageAccess(Box::new(e))),
+        };
+
+        let salt = match &existing {
+            Some(bytes) => parse_header(bytes)?,
+            None => {
+                let mut salt = [0u8; SALT_LEN];
+                OsRng.fill_bytes(&mut salt);
+                salt
+            }
+        };
+        let session = matches!(key, FileKey::Session(_));
+        let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&derive_key(key, &salt)?[..]));
+
+        let mut set_aside = None;
+        let plaintext = match &existing {
+            Some(bytes) => {
+                let nonce = Nonce::from_slice(&bytes[HEADER_LEN - NONCE_LEN..HEADER_LEN]);
+                match cipher.decrypt(nonce, &bytes[HEADER_LEN..]) {
+                    Ok(plaintext) => Some(Zeroizing::new(plaintext)),
+                    // Left over from an earlier session; kept rather than overwritten
+                    Err(_) if session => {
+                        set_aside = Some(set_aside_unreadable(path)?);
+                        None
+                    }
+                    Err(_) => {
+                        return Err(Error::Invalid(
+                            "key".to_string(),
+                            "cannot decrypt the credential file (wrong passphrase or corrupted file)".to_string(),
+                        ))
+                    }
+                }
+            }
+            None => None,
+        };
+
+        let entries = match plaintext {
+            Some(plaintext) => {
+                let records: Vec<Record> = serde_json::from_slice(&plaintext)
+                    .map_err(|e| Error::PlatformFailure(Box::new(e)))?;
+                records
+                    .into_iter()
+                    .map(|r| {
+                        let secret = BASE64.decode(&*r.secret).map_err(|e| Error::PlatformFailure(Box::new(e)))?;
+                        Ok(((r.service, r.user), Zeroizing::new(secret)))
+                    })
+                    .collect::<Result<Entries>>()?
+            }
+            None => Entries::new(),
+        };
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            salt,
+            cipher,
+            entries: Mutex::new(entries),
+            set_aside,
+        })
+    }
+
+    /// Encrypt and atomically replace the file with the given entries
+    fn persist(&self, entries: &Entries) -> Result<()> {
+        let records: Vec<Record> = entries
+            .iter()
+            .map(|((service, user), secret)| Record {
+                service: service.clone(),
+                user: user.clone(),
+                secret: Zeroizing::new(BASE64.encode(&secret[..])),
+            })
+            .collect();
+        let plaintext = crate::encoding::to_json(&records).map_err(|e| Error::PlatformFailure(Box::new(e)))?;
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .cipher
+            .encrypt(&nonce, plaintext.as_ref())
+            .map_err(|e| Error::PlatformFailure(e.to_string().into()))?;
+
+        let mut contents = Vec::with_capacity(HEADER_LEN + ciphertext.len());
+        contents.extend_from_slice(MAGIC);
+        contents.push(VERSION);
+        contents.extend_from_slice(&self.salt);
+        contents.extend_from_slice(&nonce);
+        contents.extend_from_slice(&ciphertext);
+
+        let io_error = |e: std::io::Error| Error::NoStorageAccess(Box::new(e));
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent).map_err(io_error)?;
+        }
+        let tmp_path = self.path.with_extension("tmp");
+        std::fs::write(&tmp_path, contents).map_err(io_error)?;
+        std::fs::rename(&tmp_path, &self.path).map_err(io_error)
+    }
+}
+
+impl std::fmt::Debug for Vault {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Vault").field("path", &self.path).finish()
+    }
+}
+
+/// Rename a file that cannot be decrypted out of the way, returning its new path
+fn set_aside_unreadable(path: &Path) -> Result<PathBuf> {
+    let elapsed = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".unreadable-{}", elapsed.as_secs()));
+    let aside = path.with_file_name(name);
+    std::fs::rename(path, &aside).map_err(|e| Error::NoStorageAccess(Box::new(e)))?;
+    Ok(aside)
+}
+
+fn parse_header(bytes: &[u8]) -> Result<[u8; SALT_LEN]> {
+    if bytes.len() < HEADER_LEN || &bytes[..MAGIC.len()] != MAGIC {
+        return Err(Error::Invalid(
+            "file".to_string(),
+            "not an encrypted credential file".to_string(),
+        ));
+    }
+    if bytes[MAGIC.len()] != VERSION {
+        return Err(Error::Invalid(
+            "file".to_string(),
+            format!("unsupported format version {}", bytes[MAGIC.len()]),
+        ));
+    }
+    let mut salt = [0u8; SALT_LEN];
+    salt.copy_from_slice(&bytes[MAGIC.len() + 1..MAGIC.len() + 1 + SALT_LEN]);
+    Ok(salt)
+}
+
+fn derive_key(key: FileKey, salt: &[u8]) -> Result<Zeroizing<[u8; 32]>> {
+    match key {
+        FileKey::Passphrase(passphrase) => {
+            let mut bytes = Zeroizing::new([0u8; 32]);
+            argon2::Argon2::default()
+                .hash_password_into(passphrase.as_bytes(), salt, &mut bytes[..])
+                .map_err(|e| Error::Invalid("passphrase".to_string(), e.to_string()))?;
+            Ok(bytes)
+        }
+        FileKey::Raw(bytes) | FileKey::Session(bytes) => Ok(bytes),
+    }
+}
+
+/// A credential in the encrypted file
+#[derive(Debug)]
+pub struct Cred {
+    vault: Arc<Vault>,
+    service: String,
+    user: String,
+}
+
+impl Cred {
+    fn key(&self) -> (String, String) {
+        (self.service.clone(), self.user.clone())
+    }
+}
+
+impl CredentialApi for Cred {
+    fn set_secret(&self, secret: &[u8]) -> Result<()> {
+        let mut entries = self.vault.entries.lock().unwrap();
+        let previous = entries.insert(self.key(), Zeroizing::new(secret.to_vec()));
+        if let Err(e) = self.vault.persist(&entries) {
+            // Keep memory consistent with what is on disk
+            match previous {
+                Some(previous) => entries.insert(self.key(), previous),
+                None => entries.remove(&self.key()),
+            };
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    fn get_secret(&self) -> Result<Vec<u8>> {
+        let entries = self.vault.entries.lock().unwrap();
+        // The caller owns the returned copy; the stored one stays wrapped
+        entries.get(&self.key()).map(|secret| secret.to_vec()).ok_or(Error::NoEntry)
+    }
+
+    fn delete_credential(&self) -> Result<()> {
+        let mut entries = self.vault.entries.lock().unwrap();
+        let previous = entries.remove(&self.key()).ok_or(Error::NoEntry)?;
+        if let Err(e) = self.vault.persist(&entries) {
+            entries.insert(self.key(), previous);
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    fn get_credential(&self) -> Result<Option<Arc<Credential>>> {
+        let entries = self.vault.entries.lock().unwrap();
+        match entries.contains_key(&self.key()) {
+            true => Ok(None),
+            false => Err(Error::NoEntry),
+        }
+    }
+
+    fn get_specifiers(&self) -> Option<(String, String)> {
+        Some(self.key())
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+/// The builder for encrypted file credentials
+#[derive(Debug)]
+pub struct Store {
+    id: String,
+    vault: Arc<Vault>,
+}
+
+impl Store {
+    /// Open (or prepare to create) the credential file at `path`
+    pub fn new(path: &Path, key: FileKey) -> Result<Arc<Self>> {
+        let elapsed = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        Ok(Arc::new(Self {
+            id: format!("{}, opened at {}", path.display(), elapsed.as_secs_f64()),
+            vault: Arc::new(Vault::open(path, key)?),
+        }))
+    }
+
+    /// Where the file was moved on opening because a [`FileKey::Session`] key
+    /// could not decrypt it
+    pub fn set_aside(&self) -> Option<&Path> {
+        self.vault.set_aside.as_deref()
+    }
+}
+
+impl CredentialStoreApi for Store {
+    fn vendor(&self) -> String {
+        String::from("Encrypted file store, tauri-plugin-keyring")
+    }
+
+    fn id(&self) -> String {
+        self.id.clone()
+    }
+
+    /// No modifiers are supported.
+    fn build(
+        &self,
+        service: &str,
+        user: &str,
+        modifiers: Option<&HashMap<&str, &str>>,
+    ) -> Result<Entry> {
+        if modifiers.is_some_and(|m| !m.is_empty()) {
+            return Err(Error::NotSupportedByStore(
+                "The encrypted file store doesn't allow modifiers".to_string(),
+            ));
+        }
+        Ok(Entry::new_with_credential(Arc::new(Cred {
+            vault: self.vault.clone(),
+            service: service.to_string(),
+            user: user.to_string(),
+        })))
+    }
+
+    /// Matches on the `service` and `user` keys; an empty spec returns every entry.
+    fn search(&self, spec: &HashMap<&str, &str>) -> Result<Vec<Entry>> {
+        if let Some(key) = spec.keys().find(|k| !matches!(**k, "service" | "user")) {
+            return Err(Error::Invalid(key.to_string(), "unknown search key".to_string()));
+        }
+        let entries = self.vault.entries.lock().unwrap();
+        Ok(entries
+            .keys()
+            .filter(|(service, user)| {
+                spec.get("service").map_or(true, |s| s == service)
+                    && spec.get("user").map_or(true, |u| u == user)
+            })
+            .map(|(service, user)| {
+                Entry::new_with_credential(Arc::new(Cred {
+                    vault: self.vault.clone(),
+                    service: service.clone(),
+                    user: user.clone(),
+                }))
+            })
+            .collect())
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_path(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join("tauri-plugin-keyring-tests");
+        let path = dir.join(format!("{}-{}.bin", name, std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    #[test]
+    fn test_round_trip_and_reopen() {
+        let path = temp_path("round-trip");
+        let store = Store::new(&path, FileKey::Passphrase("hunter2".to_string().into())).unwrap();
+        let entry = store.build("service", "user", None).unwrap();
+        entry.set_secret(&[1, 2, 3]).unwrap();
+        assert_eq!(entry.get_secret().unwrap(), vec![1, 2, 3]);
+
+        // A fresh store over the same file sees the persisted entry
+        let reopened = Store::new(&path, FileKey::Passphrase("hunter2".to_string().into())).unwrap();
+        let entry = reopened.build("service", "user", None).unwrap();
+        assert_eq!(entry.get_secret().unwrap(), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_wrong_passphrase_is_rejected() {
+        let path = temp_path("wrong-passphrase");
+        let store = Store::new(&path, FileKey::Passphrase("right".to_string().into())).unwrap();
+        store.build("service", "user", None).unwrap().set_password("secret").unwrap();
+
+        assert!(Store::new(&path, FileKey::Passphrase("wrong".to_string().into())).is_err());
+    }
+
+    #[test]
+    fn test_session_key_sets_earlier_file_aside() {
+        let path = temp_path("session");
+        let store = Store::new(&path, FileKey::Session([1u8; 32].into())).unwrap();
+        store.build("service", "user", None).unwrap().set_password("secret").unwrap();
+        assert_eq!(store.set_aside(), None);
+
+        // A key from a later session moves the file aside and starts an empty one
+        let store = Store::new(&path, FileKey::Session([2u8; 32].into())).unwrap();
+        let aside = store.set_aside().unwrap().to_path_buf();
+        assert!(aside.file_name().unwrap().to_string_lossy().contains(".unreadable-"));
+        assert!(!path.exists());
+        let entry = store.build("service", "user", None).unwrap();
+        assert!(matches!(entry.get_password(), Err(Error::NoEntry)));
+        entry.set_password("again").unwrap();
+
+        // The earlier file is intact and still opens with its own key
+        let earlier = Store::new(&aside, FileKey::Raw([1u8; 32].into())).unwrap();
+        assert_eq!(earlier.build("service", "user", None).unwrap().get_password().unwrap(), "secret");
+        std::fs::remove_file(&aside).unwrap();
+
+        assert!(Store::new(&path, FileKey::Raw([1u8; 32].into())).is_err());
+        let reopened = Store::new(&path, FileKey::Session([2u8; 32].into())).unwrap();
+        assert_eq!(reopened.build("service", "user", None).unwrap().get_password().unwrap(), "again");
+    }
+
+    #[test]
+    fn test_file_does_not_contain_plaintext() {
+        let path = temp_path("plaintext");
+        let store = Store::new(&path, FileKey::Raw([7u8; 32].into())).unwrap();
+        store.build("service", "user", None).unwrap().set_password("plaintext-marker").unwrap();
+
+        let contents = std::fs::read(&path).unwrap();
+        assert!(contents.starts_with(MAGIC));
+        assert!(!contents.windows(16).any(|w| w == b"plaintext-marker"));
+    }
+
+    #[test]
+    fn test_delete_and_search() {
+        let path = temp_path("search");
+        let store = Store::new(&path, FileKey::Raw([1u8; 32].into())).unwrap();
+        store.build("a", "one", None).unwrap().set_password("1").unwrap();
+        store.build("a", "two", None).unwrap().set_password("2").unwrap();
+        store.build("b", "one", None).unwrap().set_password("3").unwrap();
+
+        let found = store.search(&HashMap::from([("service", "a")])).unwrap();
+        assert_eq!(found.len(), 2);
+
+        let entry = store.build("a", "one", None).unwrap();
+        entry.delete_credential().unwrap();
+        assert!(matches!(entry.get_password(), Err(Error::NoEntry)));
+        assert!(matches!(entry.delete_credential(), Err(Error::NoEntry)));
+        assert_eq!(store.search(&HashMap::from([("service", "a")])).unwrap().len(), 1);
+    }
+}
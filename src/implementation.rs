@@ -1,102 +1,710 @@
+use std::collections::{BTreeSet, HashMap};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
 use keyring_core::Entry;
-use std::sync::OnceLock;
-use base64::{Engine as _, engine::general_purpose::STANDARD as BASE64};
-use crate::models::{CredentialType, CredentialValue};
+use serde::{de::DeserializeOwned, Serialize};
+use zeroize::Zeroizing;
+use log::LevelFilter;
+use crate::chunks::ChunkEntries;
+use crate::encoding::{self, StoredFormat};
+use crate::index::EntryIndex;
+use crate::metadata::{self, Metadata, MetadataEntry};
+use crate::models::{AccessPolicy, CredentialRecord, CredentialType, CredentialValue, LogRedaction, NamingMigration, NamingScheme, PasswordPolicy, SecretCompression, SecretEncoding, StoreBackend};
+use crate::random;
+use crate::redaction::RedactionKey;
+
+/// An identifier as it should appear in log output
+struct Redacted<'a> {
+    value: &'a str,
+    mode: LogRedaction,
+    key: &'a RedactionKey,
+}
+
+impl std::fmt::Display for Redacted<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.mode {
+            LogRedaction::None => f.write_str(self.value),
+            LogRedaction::Hash => f.write_str(self.key.hash(self.value).as_deref().unwrap_or("<redacted>")),
+            LogRedaction::Omit => f.write_str("<redacted>"),
+        }
+    }
+}
+
+/// Behaviour shared by every entry an implementation touches
+#[derive(Debug, Clone)]
+pub struct KeyringOptions {
+    pub log_level: LevelFilter,
+    pub access_policy: Option<AccessPolicy>,
+    pub secret_encoding: SecretEncoding,
+    pub secret_compression: SecretCompression,
+    pub naming_scheme: NamingScheme,
+    /// Values larger than this many bytes are split across several entries
+    pub chunk_size: Option<usize>,
+    pub log_redaction: LogRedaction,
+    /// Usernames are hashed with this key when `log_redaction` is `Hash`
+    pub redaction_key: RedactionKey,
+    pub backend: StoreBackend,
+}
+
+impl Default for KeyringOptions {
+    fn default() -> Self {
+        Self {
+            log_level: LevelFilter::Trace,
+            access_policy: None,
+            secret_encoding: SecretEncoding::default(),
+            secret_compression: SecretCompression::default(),
+            naming_scheme: NamingScheme::default(),
+            chunk_size: None,
+            log_redaction: LogRedaction::default(),
+            redaction_key: RedactionKey::generate(),
+            backend: StoreBackend::default(),
+        }
+    }
+}
+
+impl KeyringOptions {
+    /// Check the options before any entry is touched
+    pub(crate) fn validate(&self) -> crate::Result<()> {
+        if self.chunk_size == Some(0) {
+            return Err(crate::Error::InvalidInput("Chunk size must be at least one byte".into()));
+        }
+        if self.secret_compression == SecretCompression::Deflate && !cfg!(feature = "compression") {
+            return Err(crate::Error::InvalidInput("Deflate compression requires the `compression` feature".into()));
+        }
+        self.naming_scheme.validate()
+    }
+}
+
+/// The service name resolved from the plugin config, which the frontend may
+/// override once through `initialize_service`
+pub(crate) struct ServiceName {
+    configured: String,
+    initialized: OnceLock<String>,
+}
+
+impl ServiceName {
+    pub(crate) fn new(configured: String) -> Self {
+        Self { configured, initialized: OnceLock::new() }
+    }
+
+    /// The override if one was set, otherwise the configured name
+    pub(crate) fn get(&self) -> &str {
+        self.initialized.get().unwrap_or(&self.configured)
+    }
 
-static SERVICE_NAME: OnceLock<String> = OnceLock::new();
+    /// Override the configured name. Only the first call succeeds, so a
+    /// webview cannot later redirect operations to another service.
+    pub(crate) fn initialize(&self, service_name: String) -> crate::Result<()> {
+        self.initialized
+            .set(service_name)
+            .map_err(|_| crate::Error::InvalidInput("Service name already initialized".into()))
+    }
+}
+
+/// An entry holding (part of) a credential value, protected by `access_policy`
+pub(crate) fn new_entry(service: &str, user: &str, access_policy: Option<AccessPolicy>) -> crate::Result<Entry> {
+    // Only the iOS keychain store understands the access-policy modifier
+    match access_policy {
+        Some(policy) if cfg!(target_os = "ios") => {
+            let modifiers = HashMap::from([("access-policy", policy.as_modifier())]);
+            Entry::new_with_modifiers(service, user, &modifiers).map_err(Into::into)
+        },
+        _ => Entry::new(service, user).map_err(Into::into),
+    }
+}
 
-pub struct KeyringImplementation;
+pub struct KeyringImplementation {
+    service: String,
+    options: KeyringOptions,
+    /// Expired credentials removed while reading, until taken by `take_expired`
+    expired: Mutex<Vec<(String, CredentialType)>>,
+}
 
 impl KeyringImplementation {
-    /// Initialize the service name for keyring entries
-    pub fn initialize_service(service_name: String) -> crate::Result<()> {
-        tauri_plugin_log::log::info!("Initializing keyring service: {}", service_name);
-        SERVICE_NAME.set(service_name)
-            .map_err(|_| crate::Error::InvalidInput("Service name already initialized".into()))?;
+    /// Create an implementation bound to the given service name and options
+    pub fn new(service: impl Into<String>, options: KeyringOptions) -> Self {
+        Self { service: service.into(), options, expired: Mutex::new(Vec::new()) }
+    }
+    
+    /// The expired credentials removed since the last call, so the caller can
+    /// report their removal
+    pub(crate) fn take_expired(&self) -> Vec<(String, CredentialType)> {
+        std::mem::take(&mut *self.expired.lock().unwrap())
+    }
+    
+    /// Remove a credential found to be expired and remember it for `take_expired`
+    fn remove_expired(&self, username: &str, credential_type: &CredentialType, entry: &Entry) -> crate::Result<()> {
+        keyring_log!(self, Info, "Removing expired {} for user: {}", credential_type, self.redact(username));
+        self.remove(username, credential_type, entry)?;
+        self.expired.lock().unwrap().push((username.to_string(), credential_type.clone()));
         Ok(())
     }
     
-    /// Get the initialized service name
-    fn get_service_name() -> crate::Result<&'static String> {
-        SERVICE_NAME.get()
-            .ok_or(crate::Error::InvalidInput("Service name not initialized".into()))
+    /// Run an operation inside a `tracing` span recording its latency and outcome
+    #[cfg(feature = "tracing")]
+    fn traced<T>(
+        &self,
+        operation: &'static str,
+        credential_type: Option<CredentialType>,
+        f: impl FnOnce() -> crate::Result<T>,
+    ) -> crate::Result<T> {
+        let span = tracing::info_span!(
+            "keyring",
+            operation,
+            service = %self.service,
+            credential_type = tracing::field::Empty,
+            backend = ?self.options.backend,
+            latency_us = tracing::field::Empty,
+            outcome = tracing::field::Empty,
+        );
+        if let Some(credential_type) = credential_type {
+            span.record("credential_type", tracing::field::display(credential_type));
+        }
+        
+        let start = std::time::Instant::now();
+        let result = span.in_scope(f);
+        span.record("latency_us", start.elapsed().as_micros() as u64);
+        span.record("outcome", match &result {
+            Ok(_) => "ok",
+            Err(crate::Error::EntryNotFound) => "not_found",
+            Err(_) => "error",
+        });
+        result
+    }
+    
+    #[cfg(not(feature = "tracing"))]
+    fn traced<T>(
+        &self,
+        _operation: &'static str,
+        _credential_type: Option<CredentialType>,
+        f: impl FnOnce() -> crate::Result<T>,
+    ) -> crate::Result<T> {
+        f()
+    }
+    
+    /// A username as it should appear in log output
+    fn redact<'a>(&'a self, username: &'a str) -> Redacted<'a> {
+        Redacted { value: username, mode: self.options.log_redaction, key: &self.options.redaction_key }
+    }
+    
+    /// The `service/username/type` name metadata is keyed by, whatever the naming scheme
+    fn entry_username(&self, username: &str, credential_type: &CredentialType) -> String {
+        format!("{}/{}/{}", self.service, username, credential_type)
+    }
+    
+    /// Create a keyring entry for the credential, named by the configured scheme
+    fn create_entry(&self, username: &str, credential_type: &CredentialType) -> crate::Result<Entry> {
+        let (entry_service, entry_user) = self.options.naming_scheme.locate(&self.service, username, credential_type)?;
+        keyring_log!(self, Debug, "Creating keyring entry for: {}/{}/{}", self.service, self.redact(username), credential_type);
+        new_entry(&entry_service, &entry_user, self.options.access_policy)
+    }
+    
+    /// The companion entry holding the credential's metadata
+    fn metadata_entry(&self, username: &str, credential_type: &CredentialType) -> crate::Result<MetadataEntry> {
+        MetadataEntry::new(&self.service, &self.entry_username(username, credential_type), self.options.chunk_size, self.options.access_policy)
     }
     
-    /// Create a keyring entry with the format: service_name/username/credential_type
-    fn create_entry(username: &str, credential_type: &CredentialType) -> crate::Result<Entry> {
-        let service = Self::get_service_name()?;
-        let entry_username = format!("{}/{}/{}", service, username, credential_type);
-        tauri_plugin_log::log::debug!("Creating keyring entry for: {}", entry_username);
-        Entry::new(service, &entry_username).map_err(Into::into)
+    /// The companion entries a credential is split across when it is too large for one
+    fn chunk_entries(&self, username: &str, credential_type: &CredentialType) -> ChunkEntries {
+        ChunkEntries::new(&self.service, &self.entry_username(username, credential_type), self.options.access_policy)
     }
     
-    /// Set a credential (password or secret)
+    /// The index of the entries stored for this service
+    fn index(&self) -> crate::Result<EntryIndex> {
+        EntryIndex::new(&self.service, self.options.chunk_size, self.options.access_policy)
+    }
+    
+    /// Set a credential (password, secret or JSON)
     pub fn set(&self, username: &str, credential_type: CredentialType, value: CredentialValue) -> crate::Result<()> {
-        tauri_plugin_log::log::info!("Setting {} for user: {}", credential_type, username);
-        let entry = Self::create_entry(username, &credential_type)?;
+        self.write(username, credential_type, value, None)
+    }
+    
+    /// Set a credential that reads as missing once `ttl` has elapsed
+    pub fn set_with_ttl(&self, username: &str, credential_type: CredentialType, value: CredentialValue, ttl: Duration) -> crate::Result<()> {
+        let expires_at = metadata::now().saturating_add(ttl.as_secs());
+        self.write(username, credential_type, value, Some(expires_at))
+    }
+    
+    /// Store `length` random bytes as the user's secret
+    pub fn generate_secret(&self, username: &str, length: usize) -> crate::Result<()> {
+        let secret = random::secret(length)?;
+        self.set(username, CredentialType::Secret, CredentialValue::Secret(secret))
+    }
+    
+    /// Read the secret stored for `username`, storing `length` random bytes on
+    /// first use if `create` is set. Unlike `generate_secret` no metadata or
+    /// index record is written, so the key is a single item in the store.
+    #[cfg(feature = "encrypted-file")]
+    pub(crate) fn key(&self, username: &str, length: usize, create: bool) -> crate::Result<Zeroizing<Vec<u8>>> {
+        self.traced("key", Some(CredentialType::Secret), || {
+            let entry = self.create_entry(username, &CredentialType::Secret)?;
+            match self.load(username, &CredentialType::Secret, &entry) {
+                Ok(stored) => self.decode_secret(stored),
+                Err(crate::Error::EntryNotFound) if create => {
+                    keyring_log!(self, Info, "Creating key for user: {}", self.redact(username));
+                    let key = random::secret(length)?;
+                    self.store(username, &CredentialType::Secret, &entry, &self.encode_secret(&key), None)?;
+                    Ok(key)
+                },
+                Err(e) => Err(e),
+            }
+        })
+    }
+    
+    /// Store a random password following `policy` as the user's password
+    pub fn generate_password(&self, username: &str, policy: &PasswordPolicy) -> crate::Result<()> {
+        let password = random::password(policy)?;
+        self.set(username, CredentialType::Password, CredentialValue::Password(password))
+    }
+    
+    fn write(&self, username: &str, credential_type: CredentialType, value: CredentialValue, expires_at: Option<u64>) -> crate::Result<()> {
+        self.traced("set", Some(credential_type.clone()), || {
+            keyring_log!(self, Info, "Setting {} for user: {}", credential_type, self.redact(username));
+            let entry = self.create_entry(username, &credential_type)?;
+            let metadata_entry = self.metadata_entry(username, &credential_type)?;
+            let metadata = match metadata_entry.load() {
+                Ok(metadata) => metadata.unwrap_or_default(),
+                Err(e) => {
+                    keyring_log!(self, Warn, "Replacing unreadable metadata for user: {}: {}", self.redact(username), e);
+                    Metadata::default()
+                },
+            };
         
-        match (credential_type, value) {
-            (CredentialType::Password, CredentialValue::Password(password)) => {
-                entry.set_password(&password).map_err(Into::into)
-            },
-            (CredentialType::Secret, CredentialValue::Secret(secret)) => {
-                let encoded = BASE64.encode(&secret);
-                entry.set_secret(encoded.as_bytes()).map_err(Into::into)
-            },
-            _ => Err(crate::Error::InvalidInput("Credential type and value type mismatch".into()))
+            let chunks = match (&credential_type, value) {
+                (CredentialType::Password, CredentialValue::Password(password)) => {
+                    self.store(username, &credential_type, &entry, password.as_bytes(), metadata.chunks)?
+                },
+                (CredentialType::Secret, CredentialValue::Secret(secret)) => {
+                    self.store(username, &credential_type, &entry, &self.encode_secret(&secret), metadata.chunks)?
+                },
+                (CredentialType::Json, CredentialValue::Json(value)) => {
+                    let json = encoding::to_json(&value)?;
+                    self.store(username, &credential_type, &entry, &json, metadata.chunks)?
+                },
+                _ => return Err(crate::Error::InvalidInput("Credential type and value type mismatch".into()))
+            };
+        
+            self.touch_metadata(&metadata_entry, metadata, chunks, expires_at)?;
+            self.index()?.insert(username, &credential_type)
+        })
+    }
+    
+    /// Write a value to the credential's entry, splitting it across chunk
+    /// entries when it is larger than the configured chunk size. `previous_chunks`
+    /// is how many chunks the value being replaced had, if known.
+    fn store(
+        &self,
+        username: &str,
+        credential_type: &CredentialType,
+        entry: &Entry,
+        value: &[u8],
+        previous_chunks: Option<usize>,
+    ) -> crate::Result<usize> {
+        let chunks = self.chunk_entries(username, credential_type)
+            .store(value, self.options.chunk_size, previous_chunks, |stored| self.store_raw(credential_type, entry, stored))?;
+        if chunks > 0 {
+            keyring_log!(self, Debug, "Split {} for user: {} into {} chunks", credential_type, self.redact(username), chunks);
         }
+        Ok(chunks)
+    }
+    
+    /// Write bytes to an entry the way its credential type is read back
+    fn store_raw(&self, credential_type: &CredentialType, entry: &Entry, stored: &[u8]) -> crate::Result<()> {
+        match credential_type {
+            CredentialType::Secret => entry.set_secret(stored)?,
+            _ => entry.set_password(std::str::from_utf8(stored).map_err(|_| crate::Error::InvalidUtf8)?)?,
+        }
+        Ok(())
+    }
+    
+    /// Read the value of the credential's entry, reassembling it if it was split
+    fn load(&self, username: &str, credential_type: &CredentialType, entry: &Entry) -> crate::Result<Zeroizing<Vec<u8>>> {
+        let stored = match credential_type {
+            CredentialType::Secret => Zeroizing::new(entry.get_secret()?),
+            _ => Zeroizing::new(entry.get_password()?.into_bytes()),
+        };
+        self.chunk_entries(username, credential_type).load(stored)
+    }
+    
+    /// Record a write of `chunks` chunks in the credential's metadata, keeping its label and comment
+    fn touch_metadata(&self, metadata_entry: &MetadataEntry, mut metadata: Metadata, chunks: usize, expires_at: Option<u64>) -> crate::Result<()> {
+        let now = metadata::now();
+        metadata.created_at.get_or_insert(now);
+        metadata.updated_at = Some(now);
+        metadata.expires_at = expires_at;
+        metadata.chunks = Some(chunks);
+        metadata_entry.store(&metadata)
     }
     
     /// Get a credential (password or secret)
     pub fn get(&self, username: &str, credential_type: CredentialType) -> crate::Result<CredentialValue> {
-        tauri_plugin_log::log::debug!("Getting {} for user: {}", credential_type, username);
-        let entry = Self::create_entry(username, &credential_type)?;
+        self.traced("get", Some(credential_type.clone()), || {
+            keyring_log!(self, Debug, "Getting {} for user: {}", credential_type, self.redact(username));
+            let entry = self.create_entry(username, &credential_type)?;
         
-        match credential_type {
-            CredentialType::Password => {
-                let password = entry.get_password().map_err(crate::Error::from)?;
-                Ok(CredentialValue::Password(password))
+            if self.is_expired(username, &credential_type) {
+                self.remove_expired(username, &credential_type, &entry)?;
+                return Err(crate::Error::EntryNotFound);
+            }
+        
+            let stored = self.load(username, &credential_type, &entry)?;
+            match credential_type {
+                CredentialType::Password => Ok(CredentialValue::Password(into_text(stored)?)),
+                CredentialType::Secret => Ok(CredentialValue::Secret(self.decode_secret(stored)?)),
+                CredentialType::Json => {
+                    let json = into_text(stored)?;
+                    Ok(CredentialValue::Json(serde_json::from_str(&json)?))
+                }
+            }
+        })
+    }
+    
+    /// Secret bytes as written to the store
+    fn encode_secret(&self, secret: &[u8]) -> Zeroizing<Vec<u8>> {
+        encoding::encode(secret, self.options.secret_encoding, self.options.secret_compression)
+    }
+    
+    /// Secret bytes as read back from the store
+    fn decode_secret(&self, stored: Zeroizing<Vec<u8>>) -> crate::Result<Zeroizing<Vec<u8>>> {
+        encoding::decode(stored)
+    }
+    
+    /// Whether the credential's metadata marks it as expired
+    fn is_expired(&self, username: &str, credential_type: &CredentialType) -> bool {
+        match self.metadata_entry(username, credential_type).and_then(|entry| entry.load()) {
+            Ok(metadata) => metadata.is_some_and(|metadata| metadata.is_expired(metadata::now())),
+            Err(e) => {
+                keyring_log!(self, Warn, "Ignoring unreadable metadata for user: {}: {}", self.redact(username), e);
+                false
             },
-            CredentialType::Secret => {
-                let encoded_bytes = entry.get_secret().map_err(crate::Error::from)?;
-                let encoded_str = String::from_utf8(encoded_bytes)
-                    .map_err(|_| crate::Error::InvalidUtf8)?;
-                let secret = BASE64.decode(encoded_str)
-                    .map_err(|_| crate::Error::InvalidInput("Invalid base64 data".into()))?;
-                Ok(CredentialValue::Secret(secret))
+        }
+    }
+    
+    /// Remove a credential along with its chunks, metadata and index record
+    fn remove(&self, username: &str, credential_type: &CredentialType, entry: &Entry) -> crate::Result<()> {
+        match entry.delete_credential() {
+            Ok(()) | Err(keyring_core::Error::NoEntry) => {},
+            Err(e) => return Err(e.into()),
+        }
+        self.chunk_entries(username, credential_type).delete_from(0, None)?;
+        self.metadata_entry(username, credential_type)?.delete()?;
+        self.index()?.remove(username, credential_type)
+    }
+    
+    /// Get a credential along with its timestamps, label and comment
+    pub fn get_with_metadata(&self, username: &str, credential_type: CredentialType) -> crate::Result<CredentialRecord> {
+        self.traced("get_with_metadata", Some(credential_type.clone()), || {
+            let metadata_entry = self.metadata_entry(username, &credential_type)?;
+            let value = self.get(username, credential_type)?;
+            let metadata = metadata_entry.load()?.unwrap_or_default();
+        
+            Ok(CredentialRecord {
+                value,
+                created_at: metadata.created_at,
+                updated_at: metadata.updated_at,
+                label: metadata.label,
+                comment: metadata.comment,
+                expires_at: metadata.expires_at,
+            })
+        })
+    }
+    
+    /// Replace the label and comment of an existing credential
+    pub fn set_metadata(
+        &self,
+        username: &str,
+        credential_type: CredentialType,
+        label: Option<String>,
+        comment: Option<String>,
+    ) -> crate::Result<()> {
+        self.traced("set_metadata", Some(credential_type.clone()), || {
+            keyring_log!(self, Debug, "Setting metadata of {} for user: {}", credential_type, self.redact(username));
+            if !self.exists(username, credential_type.clone())? {
+                return Err(crate::Error::EntryNotFound);
             }
+        
+            let metadata_entry = self.metadata_entry(username, &credential_type)?;
+            let metadata = metadata_entry.load()?.unwrap_or_default();
+            metadata_entry.store(&Metadata { label, comment, ..metadata })
+        })
+    }
+    
+    /// Serialize `value` and store it as a JSON credential
+    pub fn set_json<T: Serialize>(&self, username: &str, value: &T) -> crate::Result<()> {
+        let value = serde_json::to_value(value)?;
+        self.set(username, CredentialType::Json, CredentialValue::Json(value))
+    }
+    
+    /// Read a JSON credential, failing with `InvalidJson` if it does not deserialize into `T`
+    pub fn get_json<T: DeserializeOwned>(&self, username: &str) -> crate::Result<T> {
+        match self.get(username, CredentialType::Json)? {
+            CredentialValue::Json(value) => serde_json::from_value(value).map_err(Into::into),
+            _ => Err(crate::Error::InvalidInput("Expected JSON".into())),
         }
     }
     
     /// Delete a credential after checking if it exists
     pub fn delete(&self, username: &str, credential_type: CredentialType) -> crate::Result<()> {
-        tauri_plugin_log::log::info!("Deleting {} for user: {}", credential_type, username);
+        self.traced("delete", Some(credential_type.clone()), || {
+            keyring_log!(self, Info, "Deleting {} for user: {}", credential_type, self.redact(username));
         
-        // Try to get first - if it doesn't exist, consider it already deleted
-        match self.get(username, credential_type.clone()) {
-            Ok(_) => {
-                let entry = Self::create_entry(username, &credential_type)?;
-                entry.delete_credential().map_err(Into::into)
-            },
-            Err(crate::Error::EntryNotFound) => {
-                tauri_plugin_log::log::debug!("Entry already doesn't exist for user: {}", username);
-                Ok(()) // Already deleted
-            },
-            Err(e) => Err(e),
-        }
+            // Never read first, so values that no longer decode can still be deleted.
+            // A missing entry counts as already deleted, and removing it also drops
+            // stale index records for entries removed outside the plugin.
+            let entry = self.create_entry(username, &credential_type)?;
+            self.remove(username, &credential_type, &entry)
+        })
     }
     
     /// Check if a credential exists
     pub fn exists(&self, username: &str, credential_type: CredentialType) -> crate::Result<bool> {
-        tauri_plugin_log::log::debug!("Checking existence of {} for user: {}", credential_type, username);
-        match self.get(username, credential_type) {
-            Ok(_) => Ok(true),
-            Err(crate::Error::EntryNotFound) => Ok(false),
-            Err(e) => Err(e),
+        self.traced("exists", Some(credential_type.clone()), || {
+            keyring_log!(self, Debug, "Checking existence of {} for user: {}", credential_type, self.redact(username));
+            let entry = self.create_entry(username, &credential_type)?;
+            if self.is_expired(username, &credential_type) {
+                self.remove_expired(username, &credential_type, &entry)?;
+                return Ok(false);
+            }
+        
+            // Only presence matters, so the value is neither reassembled nor decoded
+            match entry.get_secret().map(Zeroizing::new) {
+                Ok(_) => Ok(true),
+                Err(keyring_core::Error::NoEntry) => Ok(false),
+                Err(e) => Err(e.into()),
+            }
+        })
+    }
+    
+    /// Delete every credential type stored for a user
+    pub fn delete_user(&self, username: &str) -> crate::Result<()> {
+        self.traced("delete_user", None, || {
+            keyring_log!(self, Info, "Deleting all credentials for user: {}", self.redact(username));
+            for credential_type in CredentialType::ALL {
+                self.delete(username, credential_type.clone())?;
+            }
+            Ok(())
+        })
+    }
+    
+    /// Delete every indexed credential for this service
+    pub fn clear_service(&self) -> crate::Result<()> {
+        self.traced("clear_service", None, || {
+            keyring_log!(self, Info, "Clearing all credentials for service: {}", self.service);
+            for (username, credential_type) in self.list()? {
+                self.delete(&username, credential_type)?;
+            }
+            Ok(())
+        })
+    }
+    
+    /// Delete every expired credential for this service, returning how many were removed
+    pub fn purge_expired(&self) -> crate::Result<usize> {
+        self.traced("purge_expired", None, || {
+            keyring_log!(self, Info, "Purging expired credentials for service: {}", self.service);
+            let mut purged = 0;
+            for (username, credential_type) in self.list()? {
+                if self.is_expired(&username, &credential_type) {
+                    let entry = self.create_entry(&username, &credential_type)?;
+                    self.remove_expired(&username, &credential_type, &entry)?;
+                    purged += 1;
+                }
+            }
+            Ok(purged)
+        })
+    }
+    
+    /// List the stored credentials for this service, sorted by username then type.
+    /// Combines the entry index with a search of the store, so entries written
+    /// before the index existed or by other tools are listed where the store
+    /// supports search.
+    pub fn list(&self) -> crate::Result<Vec<(String, CredentialType)>> {
+        self.traced("list", None, || {
+            keyring_log!(self, Debug, "Listing credentials for service: {}", self.service);
+            let mut entries = self.index()?.load()?;
+            entries.extend(self.search(&self.options.naming_scheme)?);
+            Ok(entries.into_iter().collect())
+        })
+    }
+    
+    /// Rebuild the entry index from what is actually in the store.
+    ///
+    /// Every indexed entry and every type of each username in `usernames` is
+    /// probed; stores that support search also contribute all their matches.
+    /// Returns the rebuilt listing.
+    pub fn repair_index(&self, usernames: &[String]) -> crate::Result<Vec<(String, CredentialType)>> {
+        self.traced("repair_index", None, || {
+            keyring_log!(self, Info, "Repairing entry index for service: {}", self.service);
+            let index = self.index()?;
+        
+            let mut candidates = match index.load() {
+                Ok(entries) => entries,
+                Err(e) => {
+                    keyring_log!(self, Warn, "Discarding unreadable entry index: {}", e);
+                    BTreeSet::new()
+                },
+            };
+            for username in usernames {
+                for credential_type in CredentialType::ALL {
+                    candidates.insert((username.clone(), credential_type.clone()));
+                }
+            }
+        
+            let mut entries = self.search(&self.options.naming_scheme)?;
+            for (username, credential_type) in candidates {
+                match self.create_entry(&username, &credential_type)?.get_credential() {
+                    Ok(_) => {
+                        entries.insert((username, credential_type));
+                    },
+                    Err(keyring_core::Error::NoEntry) => {},
+                    Err(e) => return Err(e.into()),
+                }
+            }
+        
+            index.replace(&entries)?;
+            Ok(entries.into_iter().collect())
+        })
+    }
+    
+    /// Rewrite every listed secret not stored in the current format with the
+    /// configured encoding and compression, returning how many were rewritten.
+    /// Values, metadata and expiry are unchanged.
+    pub fn migrate_encoding(&self) -> crate::Result<usize> {
+        self.traced("migrate_encoding", Some(CredentialType::Secret), || {
+            let current = StoredFormat::Versioned(self.options.secret_encoding, self.options.secret_compression);
+            let mut migrated = 0;
+            for (username, credential_type) in self.list()? {
+                if credential_type != CredentialType::Secret {
+                    continue;
+                }
+                let entry = self.create_entry(&username, &credential_type)?;
+                let stored = match self.load(&username, &credential_type, &entry) {
+                    Ok(stored) => stored,
+                    Err(crate::Error::EntryNotFound) => continue,
+                    Err(e) => return Err(e),
+                };
+                let format = encoding::format_of(&stored)?;
+                if format == current {
+                    continue;
+                }
+            
+                let secret = self.decode_secret(stored)?;
+                let reencoded = self.encode_secret(&secret);
+                // Secrets that compression cannot shrink are already as current as they get
+                if encoding::format_of(&reencoded)? == format {
+                    continue;
+                }
+                // Keep the chunk count recorded for the next write accurate
+                let metadata_entry = self.metadata_entry(&username, &credential_type)?;
+                let metadata = metadata_entry.load().ok().flatten();
+                let chunks = self.store(&username, &credential_type, &entry, &reencoded, metadata.as_ref().and_then(|metadata| metadata.chunks))?;
+                if let Some(metadata) = metadata {
+                    metadata_entry.store(&Metadata { chunks: Some(chunks), ..metadata })?;
+                }
+                migrated += 1;
+            }
+            keyring_log!(self, Info, "Migrated {} secrets to the current encoding", migrated);
+            Ok(migrated)
+        })
+    }
+    
+    /// Move every listed credential, and every credential found in the legacy
+    /// layout by searching, to the configured naming scheme. Entries already
+    /// moved are skipped, so an interrupted migration can simply be run again.
+    pub fn migrate_naming(&self, dry_run: bool) -> crate::Result<NamingMigration> {
+        self.traced("migrate_naming", None, || {
+            keyring_log!(self, Info, "Migrating entry names for service: {} (dry run: {})", self.service, dry_run);
+            let mut candidates: BTreeSet<_> = self.list()?.into_iter().collect();
+            candidates.extend(self.search(&NamingScheme::Legacy)?);
+        
+            let mut report = NamingMigration { dry_run, ..NamingMigration::default() };
+            if self.options.naming_scheme == NamingScheme::Legacy {
+                report.unchanged = candidates.into_iter().collect();
+                return Ok(report);
+            }
+        
+            let index = self.index()?;
+            for (username, credential_type) in candidates {
+                let (legacy_service, legacy_user) = NamingScheme::Legacy.locate(&self.service, &username, &credential_type)?;
+                let legacy = Entry::new(&legacy_service, &legacy_user)?;
+                let stored = match legacy.get_secret() {
+                    Ok(stored) => Zeroizing::new(stored),
+                    Err(keyring_core::Error::NoEntry) => {
+                        report.unchanged.push((username, credential_type));
+                        continue;
+                    },
+                    Err(e) => return Err(e.into()),
+                };
+            
+                let target = match self.create_entry(&username, &credential_type) {
+                    Ok(target) => target,
+                    Err(crate::Error::InvalidInput(e)) => {
+                        keyring_log!(self, Warn, "Not migrating {} for user: {}: {}", credential_type, self.redact(&username), e);
+                        report.conflicts.push((username, credential_type));
+                        continue;
+                    },
+                    Err(e) => return Err(e),
+                };
+                let existing = match target.get_secret() {
+                    Ok(existing) => Some(Zeroizing::new(existing)),
+                    Err(keyring_core::Error::NoEntry) => None,
+                    Err(e) => return Err(e.into()),
+                };
+                match existing {
+                    // Copied by an earlier run that stopped before removing the original
+                    Some(existing) if existing == stored => {},
+                    Some(_) => {
+                        keyring_log!(self, Warn, "Not migrating {} for user: {}, both layouts hold a value", credential_type, self.redact(&username));
+                        report.conflicts.push((username, credential_type));
+                        continue;
+                    },
+                    None if !dry_run => target.set_secret(&stored)?,
+                    None => {},
+                }
+            
+                if !dry_run {
+                    legacy.delete_credential()?;
+                    index.insert(&username, &credential_type)?;
+                }
+                report.moved.push((username, credential_type));
+            }
+            Ok(report)
+        })
+    }
+    
+    /// Credentials found through the store's search under `scheme`, or none if it cannot search
+    fn search(&self, scheme: &NamingScheme) -> crate::Result<BTreeSet<(String, CredentialType)>> {
+        let mut found = BTreeSet::new();
+        for service in scheme.services(&self.service) {
+            let spec = HashMap::from([("service", service.as_str())]);
+            match Entry::search(&spec) {
+                Ok(entries) => found.extend(entries
+                    .iter()
+                    // Some stores return entries that were created but never written
+                    .filter(|entry| entry.get_credential().is_ok())
+                    .filter_map(|entry| entry.get_specifiers())
+                    .filter_map(|(entry_service, entry_user)| scheme.parse(&self.service, &entry_service, &entry_user))),
+                Err(keyring_core::Error::NotSupportedByStore(_)) => {
+                    keyring_log!(self, Debug, "Store cannot search, repairing by probing only");
+                    return Ok(BTreeSet::new());
+                },
+                Err(e) => return Err(e.into()),
+            }
         }
+        Ok(found)
     }
 }
 
+/// Stored bytes of a password or JSON credential as text, without copying them
+fn into_text(mut stored: Zeroizing<Vec<u8>>) -> crate::Result<Zeroizing<String>> {
+    String::from_utf8(std::mem::take(&mut *stored))
+        .map(Zeroizing::new)
+        .map_err(|e| {
+            drop(Zeroizing::new(e.into_bytes()));
+            crate::Error::InvalidUtf8
+        })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -127,81 +735,27 @@ mod tests {
         let service_name = format!("com.test.app.{}", *counter);
         drop(counter);
         
-        // Since we can't reset OnceLock, we need to work around this for testing
-        // We'll create entries directly for testing
         Ok(service_name)
     }
 
-    /// Create a test entry directly using the service name
-    fn create_test_entry(service_name: &str, username: &str, credential_type: &CredentialType) -> crate::Result<Entry> {
-        let entry_username = format!("{}/{}/{}", service_name, username, credential_type);
-        Entry::new(service_name, &entry_username).map_err(Into::into)
-    }
-
-    /// Set credential for testing (bypasses static service name)
+    /// Set credential for testing
     fn test_set(service_name: &str, username: &str, credential_type: CredentialType, value: CredentialValue) -> crate::Result<()> {
-        let entry = create_test_entry(service_name, username, &credential_type)?;
-        
-        match (credential_type, value) {
-            (CredentialType::Password, CredentialValue::Password(password)) => {
-                entry.set_password(&password).map_err(Into::into)
-            },
-            (CredentialType::Secret, CredentialValue::Secret(secret)) => {
-                let encoded = BASE64.encode(&secret);
-                entry.set_secret(encoded.as_bytes()).map_err(Into::into)
-            },
-            _ => Err(crate::Error::InvalidInput("Credential type and value type mismatch".into()))
-        }
+        KeyringImplementation::new(service_name, KeyringOptions::default()).set(username, credential_type, value)
     }
 
-    /// Get credential for testing (bypasses static service name)
+    /// Get credential for testing
     fn test_get(service_name: &str, username: &str, credential_type: CredentialType) -> crate::Result<CredentialValue> {
-        let entry = create_test_entry(service_name, username, &credential_type)?;
-        
-        match credential_type {
-            CredentialType::Password => {
-                let password = entry.get_password().map_err(|e| {
-                    if matches!(e, keyring_core::Error::NoEntry) {
-                        crate::Error::EntryNotFound
-                    } else {
-                        crate::Error::from(e)
-                    }
-                })?;
-                Ok(CredentialValue::Password(password))
-            },
-            CredentialType::Secret => {
-                let encoded = entry.get_secret().map_err(|e| {
-                    if matches!(e, keyring_core::Error::NoEntry) {
-                        crate::Error::EntryNotFound
-                    } else {
-                        crate::Error::from(e)
-                    }
-                })?;
-                let secret = BASE64.decode(&encoded)
-                    .map_err(|e| crate::Error::InvalidInput(format!("Base64 decode error: {}", e)))?;
-                Ok(CredentialValue::Secret(secret))
-            }
-        }
+        KeyringImplementation::new(service_name, KeyringOptions::default()).get(username, credential_type)
     }
 
-    /// Delete credential for testing (bypasses static service name)
+    /// Delete credential for testing
     fn test_delete(service_name: &str, username: &str, credential_type: CredentialType) -> crate::Result<()> {
-        let entry = create_test_entry(service_name, username, &credential_type)?;
-        
-        match entry.delete_credential() {
-            Ok(()) => Ok(()),
-            Err(keyring_core::Error::NoEntry) => Ok(()), // Already deleted
-            Err(e) => Err(crate::Error::from(e)),
-        }
+        KeyringImplementation::new(service_name, KeyringOptions::default()).delete(username, credential_type)
     }
 
-    /// Check if credential exists for testing (bypasses static service name)
+    /// Check if credential exists for testing
     fn test_exists(service_name: &str, username: &str, credential_type: CredentialType) -> crate::Result<bool> {
-        match test_get(service_name, username, credential_type) {
-            Ok(_) => Ok(true),
-            Err(crate::Error::EntryNotFound) => Ok(false),
-            Err(e) => Err(e),
-        }
+        KeyringImplementation::new(service_name, KeyringOptions::default()).exists(username, credential_type)
     }
 
     #[test]
@@ -215,7 +769,7 @@ mod tests {
             &service_name,
             username,
             CredentialType::Password,
-            CredentialValue::Password(password.to_string()),
+            CredentialValue::Password(password.to_string().into()),
         );
         assert!(result.is_ok());
 
@@ -223,7 +777,7 @@ mod tests {
         let retrieved = test_get(&service_name, username, CredentialType::Password).unwrap();
         match retrieved {
             CredentialValue::Password(retrieved_password) => {
-                assert_eq!(retrieved_password, password);
+                assert_eq!(*retrieved_password, password);
             }
             _ => panic!("Expected password, got secret"),
         }
@@ -240,7 +794,7 @@ mod tests {
             &service_name,
             username,
             CredentialType::Secret,
-            CredentialValue::Secret(secret_data.clone()),
+            CredentialValue::Secret(secret_data.clone().into()),
         );
         assert!(result.is_ok());
 
@@ -248,7 +802,7 @@ mod tests {
         let retrieved = test_get(&service_name, username, CredentialType::Secret).unwrap();
         match retrieved {
             CredentialValue::Secret(retrieved_secret) => {
-                assert_eq!(retrieved_secret, secret_data);
+                assert_eq!(*retrieved_secret, secret_data);
             }
             _ => panic!("Expected secret, got password"),
         }
@@ -263,7 +817,7 @@ mod tests {
             &service_name,
             "testuser",
             CredentialType::Password,
-            CredentialValue::Secret(vec![1, 2, 3]),
+            CredentialValue::Secret(vec![1, 2, 3].into()),
         );
         assert!(result.is_err());
         
@@ -271,7 +825,7 @@ mod tests {
             &service_name,
             "testuser",
             CredentialType::Secret,
-            CredentialValue::Password("test".to_string()),
+            CredentialValue::Password("test".to_string().into()),
         );
         assert!(result.is_err());
     }
@@ -286,7 +840,7 @@ mod tests {
             &service_name,
             username,
             CredentialType::Password,
-            CredentialValue::Password("password123".to_string()),
+            CredentialValue::Password("password123".to_string().into()),
         ).unwrap();
 
         // Set a secret for the same user (should be separate entries)
@@ -294,7 +848,7 @@ mod tests {
             &service_name,
             username,
             CredentialType::Secret,
-            CredentialValue::Secret(vec![1, 2, 3]),
+            CredentialValue::Secret(vec![1, 2, 3].into()),
         ).unwrap();
 
         // Both should exist independently
@@ -323,7 +877,7 @@ mod tests {
             &service_name,
             username,
             CredentialType::Password,
-            CredentialValue::Password("test".to_string()),
+            CredentialValue::Password("test".to_string().into()),
         ).unwrap();
 
         // Password should exist, secret should not
@@ -341,7 +895,7 @@ mod tests {
             &service_name,
             username,
             CredentialType::Password,
-            CredentialValue::Password("test".to_string()),
+            CredentialValue::Password("test".to_string().into()),
         ).unwrap();
 
         // Verify it exists
@@ -391,13 +945,13 @@ mod tests {
             &service_name,
             username,
             CredentialType::Secret,
-            CredentialValue::Secret(binary_data.clone()),
+            CredentialValue::Secret(binary_data.clone().into()),
         ).unwrap();
 
         let retrieved = test_get(&service_name, username, CredentialType::Secret).unwrap();
         match retrieved {
             CredentialValue::Secret(retrieved_data) => {
-                assert_eq!(retrieved_data, binary_data);
+                assert_eq!(*retrieved_data, binary_data);
             }
             _ => panic!("Expected secret"),
         }
@@ -408,9 +962,9 @@ mod tests {
         let service_name = setup_with_unique_service().unwrap();
         
         // Set credentials for multiple users
-        test_set(&service_name, "user1", CredentialType::Password, CredentialValue::Password("pass1".to_string())).unwrap();
-        test_set(&service_name, "user2", CredentialType::Password, CredentialValue::Password("pass2".to_string())).unwrap();
-        test_set(&service_name, "user3", CredentialType::Secret, CredentialValue::Secret(vec![1, 2, 3])).unwrap();
+        test_set(&service_name, "user1", CredentialType::Password, CredentialValue::Password("pass1".to_string().into())).unwrap();
+        test_set(&service_name, "user2", CredentialType::Password, CredentialValue::Password("pass2".to_string().into())).unwrap();
+        test_set(&service_name, "user3", CredentialType::Secret, CredentialValue::Secret(vec![1, 2, 3].into())).unwrap();
 
         // Verify all exist independently
         assert!(test_exists(&service_name, "user1", CredentialType::Password).unwrap());
@@ -419,16 +973,30 @@ mod tests {
 
         // Verify correct retrieval
         match test_get(&service_name, "user1", CredentialType::Password).unwrap() {
-            CredentialValue::Password(pass) => assert_eq!(pass, "pass1"),
+            CredentialValue::Password(pass) => assert_eq!(*pass, "pass1"),
             _ => panic!("Expected password"),
         }
         
         match test_get(&service_name, "user2", CredentialType::Password).unwrap() {
-            CredentialValue::Password(pass) => assert_eq!(pass, "pass2"),
+            CredentialValue::Password(pass) => assert_eq!(*pass, "pass2"),
             _ => panic!("Expected password"),
         }
     }
 
+    #[test]
+    fn test_service_name_can_be_initialized_once() {
+        let name = ServiceName::new("com.test.app.configured".to_string());
+        assert_eq!(name.get(), "com.test.app.configured");
+
+        // First initialization should succeed
+        assert!(name.initialize("com.test.app.static1".to_string()).is_ok());
+        assert_eq!(name.get(), "com.test.app.static1");
+
+        // Second initialization should fail
+        assert!(name.initialize("com.test.app.static2".to_string()).is_err());
+        assert_eq!(name.get(), "com.test.app.static1");
+    }
+
     #[test]
     fn test_service_name_formatting() {
         let service_name = setup_with_unique_service().unwrap();
@@ -442,7 +1010,7 @@ mod tests {
             &service_name,
             username,
             CredentialType::Password,
-            CredentialValue::Password("test".to_string()),
+            CredentialValue::Password("test".to_string().into()),
         ).unwrap();
         
         // If our formatting is correct, this should work
@@ -450,42 +1018,665 @@ mod tests {
     }
 
     #[test]
-    fn test_static_service_initialization() {
-        setup_mock_keyring().unwrap();
+    fn test_services_are_isolated() {
+        let first_service = setup_with_unique_service().unwrap();
+        let second_service = setup_with_unique_service().unwrap();
+        let username = "testuser";
+
+        test_set(
+            &first_service,
+            username,
+            CredentialType::Password,
+            CredentialValue::Password("first".to_string().into()),
+        ).unwrap();
+
+        // The same username under another service is a separate entry
+        assert!(!test_exists(&second_service, username, CredentialType::Password).unwrap());
+
+        test_set(
+            &second_service,
+            username,
+            CredentialType::Password,
+            CredentialValue::Password("second".to_string().into()),
+        ).unwrap();
+
+        match test_get(&first_service, username, CredentialType::Password).unwrap() {
+            CredentialValue::Password(pass) => assert_eq!(*pass, "first"),
+            _ => panic!("Expected password"),
+        }
+    }
+
+    #[test]
+    fn test_raw_secret_encoding() {
+        let service_name = setup_with_unique_service().unwrap();
+        let options = KeyringOptions {
+            secret_encoding: SecretEncoding::Raw,
+            ..KeyringOptions::default()
+        };
+        let keyring = KeyringImplementation::new(service_name.clone(), options);
+        let binary_data = vec![0x00, 0xFF, 0x80, 0x7F];
+
+        keyring.set("testuser", CredentialType::Secret, CredentialValue::Secret(binary_data.clone().into())).unwrap();
+
+        // Stored bytes are not base64 encoded, only prefixed with the format header
+        let entry = Entry::new(&service_name, &format!("{}/testuser/secret", service_name)).unwrap();
+        assert_eq!(entry.get_secret().unwrap(), [b"KRS1r:".as_slice(), &binary_data].concat());
+
+        match keyring.get("testuser", CredentialType::Secret).unwrap() {
+            CredentialValue::Secret(retrieved) => assert_eq!(*retrieved, binary_data),
+            _ => panic!("Expected secret"),
+        }
+    }
+
+    #[test]
+    fn test_log_level_does_not_affect_operations() {
+        let service_name = setup_with_unique_service().unwrap();
+        let options = KeyringOptions {
+            log_level: LevelFilter::Off,
+            ..KeyringOptions::default()
+        };
+        let keyring = KeyringImplementation::new(service_name, options);
+
+        keyring.set("testuser", CredentialType::Password, CredentialValue::Password("test".to_string().into())).unwrap();
+        assert!(keyring.exists("testuser", CredentialType::Password).unwrap());
+    }
+
+    #[test]
+    fn test_list_credentials() {
+        let service_name = setup_with_unique_service().unwrap();
+        let keyring = KeyringImplementation::new(service_name.clone(), KeyringOptions::default());
+
+        keyring.set("bob", CredentialType::Secret, CredentialValue::Secret(vec![1, 2, 3].into())).unwrap();
+        keyring.set("alice", CredentialType::Password, CredentialValue::Password("pw".to_string().into())).unwrap();
+        keyring.set("nested/user", CredentialType::Password, CredentialValue::Password("pw".to_string().into())).unwrap();
+        // Probing a missing entry must not make it show up
+        assert!(!keyring.exists("carol", CredentialType::Password).unwrap());
+
+        assert_eq!(
+            keyring.list().unwrap(),
+            vec![
+                ("alice".to_string(), CredentialType::Password),
+                ("bob".to_string(), CredentialType::Secret),
+                ("nested/user".to_string(), CredentialType::Password),
+            ]
+        );
+
+        keyring.delete("bob", CredentialType::Secret).unwrap();
+        assert_eq!(keyring.list().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_index_tracks_set_and_delete() {
+        let service_name = setup_with_unique_service().unwrap();
+
+        test_set(&service_name, "alice", CredentialType::Password, CredentialValue::Password("pw".to_string().into())).unwrap();
+        test_set(&service_name, "alice", CredentialType::Secret, CredentialValue::Secret(vec![1].into())).unwrap();
+        let index = EntryIndex::new(&service_name, None, None).unwrap();
+        assert_eq!(index.load().unwrap().len(), 2);
+
+        test_delete(&service_name, "alice", CredentialType::Password).unwrap();
+        test_delete(&service_name, "alice", CredentialType::Secret).unwrap();
+        assert!(index.load().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_repair_index() {
+        let service_name = setup_with_unique_service().unwrap();
+        let keyring = KeyringImplementation::new(service_name.clone(), KeyringOptions::default());
+
+        keyring.set("alice", CredentialType::Password, CredentialValue::Password("pw".to_string().into())).unwrap();
+        keyring.set("bob", CredentialType::Password, CredentialValue::Password("pw".to_string().into())).unwrap();
+
+        // Entries written or removed behind the plugin's back
+        Entry::new(&service_name, &format!("{}/carol/secret", service_name)).unwrap()
+            .set_secret(b"c2VjcmV0").unwrap();
+        Entry::new(&service_name, &format!("{}/bob/password", service_name)).unwrap()
+            .delete_credential().unwrap();
+        assert_eq!(keyring.list().unwrap().len(), 3);
+
+        let expected = vec![
+            ("alice".to_string(), CredentialType::Password),
+            ("carol".to_string(), CredentialType::Secret),
+        ];
+        assert_eq!(keyring.repair_index(&["carol".to_string()]).unwrap(), expected);
+        assert_eq!(keyring.list().unwrap(), expected);
+    }
+
+    #[test]
+    fn test_unindexed_entries_are_listed_and_cleared() {
+        let service_name = setup_with_unique_service().unwrap();
+        let keyring = KeyringImplementation::new(service_name.clone(), KeyringOptions::default());
+
+        keyring.set("alice", CredentialType::Password, CredentialValue::Password("pw".to_string().into())).unwrap();
+        // Written before the index existed
+        Entry::new(&service_name, &format!("{}/carol/password", service_name)).unwrap()
+            .set_password("pw").unwrap();
+
+        assert_eq!(keyring.list().unwrap(), vec![
+            ("alice".to_string(), CredentialType::Password),
+            ("carol".to_string(), CredentialType::Password),
+        ]);
+        keyring.clear_service().unwrap();
+        assert!(keyring.list().unwrap().is_empty());
+        assert!(!keyring.exists("carol", CredentialType::Password).unwrap());
+    }
+
+    #[test]
+    fn test_index_username_is_not_listed() {
+        let service_name = setup_with_unique_service().unwrap();
+        let keyring = KeyringImplementation::new(service_name, KeyringOptions::default());
+
+        keyring.set(".keyring-index", CredentialType::Password, CredentialValue::Password("pw".to_string().into())).unwrap();
+        keyring.set("alice", CredentialType::Password, CredentialValue::Password("pw".to_string().into())).unwrap();
+
+        assert_eq!(keyring.repair_index(&[]).unwrap(), keyring.list().unwrap());
+        assert_eq!(keyring.list().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_delete_user() {
+        let service_name = setup_with_unique_service().unwrap();
+        let keyring = KeyringImplementation::new(service_name, KeyringOptions::default());
+
+        keyring.set("alice", CredentialType::Password, CredentialValue::Password("pw".to_string().into())).unwrap();
+        keyring.set("alice", CredentialType::Secret, CredentialValue::Secret(vec![1].into())).unwrap();
+        keyring.set("bob", CredentialType::Password, CredentialValue::Password("pw".to_string().into())).unwrap();
+
+        keyring.delete_user("alice").unwrap();
+        assert!(!keyring.exists("alice", CredentialType::Password).unwrap());
+        assert!(!keyring.exists("alice", CredentialType::Secret).unwrap());
+        assert_eq!(keyring.list().unwrap(), vec![("bob".to_string(), CredentialType::Password)]);
+
+        // Users without credentials are not an error
+        keyring.delete_user("nobody").unwrap();
+    }
+
+    #[test]
+    fn test_clear_service() {
+        let service_name = setup_with_unique_service().unwrap();
+        let other_service = setup_with_unique_service().unwrap();
+        let keyring = KeyringImplementation::new(service_name, KeyringOptions::default());
+
+        keyring.set("alice", CredentialType::Password, CredentialValue::Password("pw".to_string().into())).unwrap();
+        keyring.set("bob", CredentialType::Secret, CredentialValue::Secret(vec![1].into())).unwrap();
+        test_set(&other_service, "alice", CredentialType::Password, CredentialValue::Password("pw".to_string().into())).unwrap();
+
+        keyring.clear_service().unwrap();
+        assert!(keyring.list().unwrap().is_empty());
+        assert!(!keyring.exists("bob", CredentialType::Secret).unwrap());
+        assert!(test_exists(&other_service, "alice", CredentialType::Password).unwrap());
+    }
+
+    #[test]
+    fn test_json_credentials() {
+        #[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+        struct Token {
+            access_token: String,
+            scopes: Vec<String>,
+        }
+
+        let service_name = setup_with_unique_service().unwrap();
+        let keyring = KeyringImplementation::new(service_name, KeyringOptions::default());
+        let token = Token {
+            access_token: "abc".to_string(),
+            scopes: vec!["read".to_string()],
+        };
+
+        keyring.set_json("oauth", &token).unwrap();
+        assert_eq!(keyring.get_json::<Token>("oauth").unwrap(), token);
+        assert!(keyring.exists("oauth", CredentialType::Json).unwrap());
+        assert!(!keyring.exists("oauth", CredentialType::Password).unwrap());
+
+        match keyring.get("oauth", CredentialType::Json).unwrap() {
+            CredentialValue::Json(value) => assert_eq!(value["scopes"][0], "read"),
+            _ => panic!("Expected JSON"),
+        }
+
+        // A payload of the wrong shape is reported rather than panicking
+        assert!(matches!(keyring.get_json::<Vec<u32>>("oauth"), Err(crate::Error::InvalidJson(_))));
+    }
+
+    #[test]
+    fn test_json_type_mismatch() {
+        let service_name = setup_with_unique_service().unwrap();
+
+        let result = test_set(&service_name, "testuser", CredentialType::Json, CredentialValue::Password("{}".to_string().into()));
+        assert!(matches!(result, Err(crate::Error::InvalidInput(_))));
+    }
+
+    #[test]
+    fn test_metadata_timestamps() {
+        let service_name = setup_with_unique_service().unwrap();
+        let keyring = KeyringImplementation::new(service_name, KeyringOptions::default());
+
+        keyring.set("testuser", CredentialType::Password, CredentialValue::Password("first".to_string().into())).unwrap();
+        let first = keyring.get_with_metadata("testuser", CredentialType::Password).unwrap();
+        let created_at = first.created_at.expect("created_at is recorded");
+        assert_eq!(first.updated_at, Some(created_at));
+        assert!(first.label.is_none());
+
+        keyring.set_metadata("testuser", CredentialType::Password, Some("Work".to_string()), Some("VPN login".to_string())).unwrap();
+        keyring.set("testuser", CredentialType::Password, CredentialValue::Password("second".to_string().into())).unwrap();
+
+        let second = keyring.get_with_metadata("testuser", CredentialType::Password).unwrap();
+        assert_eq!(second.created_at, Some(created_at));
+        assert!(second.updated_at.unwrap() >= created_at);
+        assert_eq!(second.label.as_deref(), Some("Work"));
+        assert_eq!(second.comment.as_deref(), Some("VPN login"));
+        match second.value {
+            CredentialValue::Password(pass) => assert_eq!(*pass, "second"),
+            _ => panic!("Expected password"),
+        }
+
+        // Metadata goes away with the credential
+        keyring.delete("testuser", CredentialType::Password).unwrap();
+        keyring.set("testuser", CredentialType::Password, CredentialValue::Password("third".to_string().into())).unwrap();
+        assert!(keyring.get_with_metadata("testuser", CredentialType::Password).unwrap().label.is_none());
+        assert_eq!(keyring.list().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_metadata_for_missing_and_legacy_entries() {
+        let service_name = setup_with_unique_service().unwrap();
+        let keyring = KeyringImplementation::new(service_name.clone(), KeyringOptions::default());
+
+        assert!(matches!(
+            keyring.set_metadata("missing", CredentialType::Password, Some("label".to_string()), None),
+            Err(crate::Error::EntryNotFound)
+        ));
+
+        // Written without going through the plugin
+        Entry::new(&service_name, &format!("{}/legacy/password", service_name)).unwrap()
+            .set_password("pw").unwrap();
+        let record = keyring.get_with_metadata("legacy", CredentialType::Password).unwrap();
+        assert!(record.created_at.is_none());
+        assert!(record.updated_at.is_none());
+    }
+
+    #[test]
+    fn test_expired_credentials_read_as_missing() {
+        let service_name = setup_with_unique_service().unwrap();
+        let keyring = KeyringImplementation::new(service_name, KeyringOptions::default());
+
+        keyring.set_with_ttl("session", CredentialType::Password, CredentialValue::Password("token".to_string().into()), Duration::ZERO).unwrap();
+        keyring.set_with_ttl("long", CredentialType::Password, CredentialValue::Password("token".to_string().into()), Duration::from_secs(3600)).unwrap();
+
+        assert!(matches!(keyring.get("session", CredentialType::Password), Err(crate::Error::EntryNotFound)));
+        assert_eq!(keyring.take_expired(), vec![("session".to_string(), CredentialType::Password)]);
+        assert!(!keyring.exists("session", CredentialType::Password).unwrap());
+        assert!(keyring.take_expired().is_empty());
+        // Removed lazily on read
+        assert_eq!(keyring.list().unwrap(), vec![("long".to_string(), CredentialType::Password)]);
+
+        let record = keyring.get_with_metadata("long", CredentialType::Password).unwrap();
+        assert!(record.expires_at.unwrap() > record.updated_at.unwrap());
+
+        // Overwriting without a TTL makes the credential permanent again
+        keyring.set("long", CredentialType::Password, CredentialValue::Password("token".to_string().into())).unwrap();
+        assert!(keyring.get_with_metadata("long", CredentialType::Password).unwrap().expires_at.is_none());
+    }
+
+    #[test]
+    fn test_purge_expired() {
+        let service_name = setup_with_unique_service().unwrap();
+        let keyring = KeyringImplementation::new(service_name, KeyringOptions::default());
+
+        keyring.set_with_ttl("a", CredentialType::Password, CredentialValue::Password("x".to_string().into()), Duration::ZERO).unwrap();
+        keyring.set_with_ttl("b", CredentialType::Secret, CredentialValue::Secret(vec![1].into()), Duration::ZERO).unwrap();
+        keyring.set("c", CredentialType::Password, CredentialValue::Password("x".to_string().into())).unwrap();
+
+        assert_eq!(keyring.purge_expired().unwrap(), 2);
+        assert_eq!(keyring.take_expired(), vec![
+            ("a".to_string(), CredentialType::Password),
+            ("b".to_string(), CredentialType::Secret),
+        ]);
+        assert_eq!(keyring.list().unwrap(), vec![("c".to_string(), CredentialType::Password)]);
+        assert_eq!(keyring.purge_expired().unwrap(), 0);
+    }
+
+    #[test]
+    fn test_log_redaction() {
+        let key = RedactionKey::generate();
+        let redact = |mode| Redacted { value: "user@example.com", mode, key: &key }.to_string();
+
+        assert_eq!(redact(LogRedaction::None), "user@example.com");
+        assert_eq!(redact(LogRedaction::Omit), "<redacted>");
+
+        let hashed = redact(LogRedaction::Hash);
+        assert!(!hashed.contains("user"));
+        assert_eq!(hashed.len(), 13);
+        // Stable, so lines about the same user can be correlated
+        assert_eq!(hashed, redact(LogRedaction::Hash));
+        assert_ne!(hashed, Redacted { value: "other@example.com", mode: LogRedaction::Hash, key: &key }.to_string());
+        assert_eq!(Some(hashed), key.hash("user@example.com"));
+
+        // Without a key usernames are omitted rather than logged
+        let unavailable = RedactionKey::at(None, LevelFilter::Off);
+        assert_eq!(Redacted { value: "user@example.com", mode: LogRedaction::Hash, key: &unavailable }.to_string(), "<redacted>");
+    }
+
+    #[test]
+    fn test_secret_buffers_are_zeroized() {
+        let options = KeyringOptions {
+            secret_encoding: SecretEncoding::Base64,
+            ..KeyringOptions::default()
+        };
+        let keyring = KeyringImplementation::new("com.test.zeroize", options);
+
+        // Zeroizing only wipes the final allocation, so each buffer must be
+        // sized up front: a reallocation would leave an unwiped copy behind
+        let encoded = keyring.encode_secret(b"top secret");
+        assert_eq!(&encoded[..], b"KRS1b:dG9wIHNlY3JldA==");
+        assert_eq!(encoded.capacity(), 6 + base64::encoded_len(10, true).unwrap());
+
+        let decoded = keyring.decode_secret(encoded).unwrap();
+        assert_eq!(&decoded[..], b"top secret");
+        assert_eq!(decoded.capacity(), base64::decoded_len_estimate(16));
+
+        let raw = KeyringImplementation::new("com.test.zeroize", KeyringOptions::default());
+        let encoded = raw.encode_secret(b"top secret");
+        assert_eq!(encoded.capacity(), encoded.len());
+    }
+
+    #[test]
+    fn test_json_buffers_are_sized_up_front() {
+        // Zeroizing only wipes the final allocation, so serializing must not
+        // grow the buffer, or an unwiped partial copy would be left behind
+        let value = serde_json::json!({ "token": "top secret", "scopes": ["read", "write"] });
+        let json = encoding::to_json(&value).unwrap();
+        assert_eq!(json.capacity(), json.len());
+        assert_eq!(serde_json::from_slice::<serde_json::Value>(&json).unwrap(), value);
+
+        let service_name = setup_with_unique_service().unwrap();
+        let keyring = KeyringImplementation::new(service_name, KeyringOptions::default());
+        keyring.set("testuser", CredentialType::Json, CredentialValue::Json(value.clone())).unwrap();
+        let entry = keyring.create_entry("testuser", &CredentialType::Json).unwrap();
+        assert_eq!(entry.get_secret().unwrap(), json.to_vec());
+        match keyring.get("testuser", CredentialType::Json).unwrap() {
+            CredentialValue::Json(stored) => assert_eq!(stored, value),
+            _ => panic!("Expected JSON"),
+        }
+    }
+
+    #[test]
+    fn test_generate_secret_and_password() {
+        let service_name = setup_with_unique_service().unwrap();
+        let implementation = KeyringImplementation::new(&service_name, KeyringOptions::default());
+
+        implementation.generate_secret("signer", 32).unwrap();
+        match test_get(&service_name, "signer", CredentialType::Secret).unwrap() {
+            CredentialValue::Secret(secret) => assert_eq!(secret.len(), 32),
+            _ => panic!("Expected secret"),
+        }
+
+        implementation.generate_password("db", &PasswordPolicy::default()).unwrap();
+        match test_get(&service_name, "db", CredentialType::Password).unwrap() {
+            CredentialValue::Password(password) => assert_eq!(password.len(), 24),
+            _ => panic!("Expected password"),
+        }
+
+        // Invalid requests leave nothing behind
+        assert!(implementation.generate_secret("empty", 0).is_err());
+        assert!(!test_exists(&service_name, "empty", CredentialType::Secret).unwrap());
+        assert_eq!(implementation.list().unwrap().len(), 2);
+    }
+
+    #[cfg(feature = "encrypted-file")]
+    #[test]
+    fn test_key_is_a_single_item() {
+        let service_name = setup_with_unique_service().unwrap();
+        let keyring = KeyringImplementation::new(&service_name, KeyringOptions::default());
+        assert!(matches!(keyring.key(".vault-key", 32, false), Err(crate::Error::EntryNotFound)));
+        let key = keyring.key(".vault-key", 32, true).unwrap();
+        assert_eq!(key.len(), 32);
+        assert_eq!(keyring.key(".vault-key", 32, false).unwrap(), key);
+        assert_eq!(keyring.key(".vault-key", 32, true).unwrap(), key);
         
-        // Test that we can only initialize once
-        assert!(KeyringImplementation::initialize_service("com.test.app.static1".to_string()).is_ok());
+        // Only found by searching the store: the key is not indexed
+        assert!(keyring.index().unwrap().load().unwrap().is_empty());
+        let metadata = keyring.metadata_entry(".vault-key", &CredentialType::Secret).unwrap();
+        assert!(metadata.load().unwrap().is_none());
+        match keyring.get(".vault-key", CredentialType::Secret).unwrap() {
+            CredentialValue::Secret(secret) => assert_eq!(secret, key),
+            _ => panic!("Expected secret"),
+        }
+    }
+    
+    #[test]
+    fn test_migrate_encoding() {
+        let service_name = setup_with_unique_service().unwrap();
+        let keyring = KeyringImplementation::new(&service_name, KeyringOptions::default());
+        keyring.set("current", CredentialType::Secret, CredentialValue::Secret(vec![1, 2, 3].into())).unwrap();
+        keyring.set("legacy", CredentialType::Secret, CredentialValue::Secret(vec![0; 4].into())).unwrap();
+        keyring.set_metadata("legacy", CredentialType::Secret, Some("Legacy".into()), None).unwrap();
+
+        // Overwrite with the bare base64 written before the format was versioned
+        let entry = Entry::new(&service_name, &format!("{}/legacy/secret", service_name)).unwrap();
+        entry.set_secret(b"dG9wIHNlY3JldA==").unwrap();
+        match keyring.get("legacy", CredentialType::Secret).unwrap() {
+            CredentialValue::Secret(secret) => assert_eq!(&secret[..], b"top secret"),
+            _ => panic!("Expected secret"),
+        }
+
+        assert_eq!(keyring.migrate_encoding().unwrap(), 1);
+        assert_eq!(entry.get_secret().unwrap(), b"KRS1r:top secret");
+        assert_eq!(keyring.migrate_encoding().unwrap(), 0);
+
+        let record = keyring.get_with_metadata("legacy", CredentialType::Secret).unwrap();
+        assert_eq!(record.label.as_deref(), Some("Legacy"));
+        match record.value {
+            CredentialValue::Secret(secret) => assert_eq!(&secret[..], b"top secret"),
+            _ => panic!("Expected secret"),
+        }
+    }
+    
+    #[cfg(feature = "compression")]
+    #[test]
+    fn test_compressed_secrets() {
+        let service_name = setup_with_unique_service().unwrap();
+        let plain = KeyringImplementation::new(&service_name, KeyringOptions::default());
+        let document = br#"{"token":"abc","scopes":["read","write","admin"]}"#.repeat(30);
+        plain.set("document", CredentialType::Secret, CredentialValue::Secret(document.clone().into())).unwrap();
+        plain.set("random", CredentialType::Secret, CredentialValue::Secret(vec![0x9C, 0x01, 0xF3].into())).unwrap();
         
-        // Second initialization should fail
-        assert!(KeyringImplementation::initialize_service("com.test.app.static2".to_string()).is_err());
-    }
-
-    #[test]
-    fn test_uninitialized_service() {
-        setup_mock_keyring().unwrap();
-        let keyring = KeyringImplementation;
-        
-        // Operations should fail if service is not initialized (and we haven't initialized it in this test)
-        // Note: Since other tests may have initialized the global service, we'll test the error condition
-        // by checking that get_service_name works after initialization
-        
-        // This test is tricky because of the global state, so let's just verify the error message format
-        match KeyringImplementation::get_service_name() {
-            Ok(_) => {
-                // Service was already initialized by another test, which is fine
-                // Let's test that operations work
-                let result = keyring.set(
-                    "testuser",
-                    CredentialType::Password,
-                    CredentialValue::Password("test".to_string()),
-                );
-                // Should work since service is initialized
-                assert!(result.is_ok());
-            }
-            Err(crate::Error::InvalidInput(msg)) => {
-                assert_eq!(msg, "Service name not initialized");
+        let options = KeyringOptions { secret_compression: SecretCompression::Deflate, ..KeyringOptions::default() };
+        let keyring = KeyringImplementation::new(&service_name, options);
+        assert_eq!(keyring.migrate_encoding().unwrap(), 1);
+        assert_eq!(keyring.migrate_encoding().unwrap(), 0);
+        
+        let stored = Entry::new(&service_name, &format!("{}/document/secret", service_name)).unwrap().get_secret().unwrap();
+        assert!(stored.starts_with(b"KRS1R:"));
+        assert!(stored.len() < document.len());
+        // Compressed and uncompressed secrets read back the same under either setting
+        for reader in [&plain, &keyring] {
+            match reader.get("document", CredentialType::Secret).unwrap() {
+                CredentialValue::Secret(secret) => assert_eq!(&secret[..], &document[..]),
+                _ => panic!("Expected secret"),
             }
-            Err(_) => panic!("Unexpected error type"),
         }
     }
-}
\ No newline at end of file
+    
+    #[test]
+    fn test_migrate_naming() {
+        let service_name = setup_with_unique_service().unwrap();
+        let legacy = KeyringImplementation::new(&service_name, KeyringOptions::default());
+        legacy.set("alice", CredentialType::Password, CredentialValue::Password("hunter2".to_string().into())).unwrap();
+        legacy.set("alice", CredentialType::Secret, CredentialValue::Secret(vec![1, 2, 3].into())).unwrap();
+        legacy.set("bob", CredentialType::Password, CredentialValue::Password("legacy".to_string().into())).unwrap();
+        legacy.set_metadata("alice", CredentialType::Secret, Some("Signing key".into()), None).unwrap();
+        
+        let options = KeyringOptions { naming_scheme: NamingScheme::Username, ..KeyringOptions::default() };
+        let keyring = KeyringImplementation::new(&service_name, options);
+        // A value already written under the new layout is never overwritten
+        Entry::new(&service_name, "bob").unwrap().set_password("current").unwrap();
+        
+        let dry_run = keyring.migrate_naming(true).unwrap();
+        assert!(dry_run.dry_run);
+        assert_eq!(dry_run.moved, vec![
+            ("alice".to_string(), CredentialType::Password),
+            ("alice".to_string(), CredentialType::Secret),
+        ]);
+        assert_eq!(dry_run.conflicts, vec![("bob".to_string(), CredentialType::Password)]);
+        assert!(matches!(keyring.get("alice", CredentialType::Password), Err(crate::Error::EntryNotFound)));
+        
+        let report = keyring.migrate_naming(false).unwrap();
+        assert_eq!(report.moved, dry_run.moved);
+        assert_eq!(report.conflicts, dry_run.conflicts);
+        match keyring.get("alice", CredentialType::Password).unwrap() {
+            CredentialValue::Password(password) => assert_eq!(&*password, "hunter2"),
+            _ => panic!("Expected password"),
+        }
+        assert!(Entry::new(&format!("{}.secret", service_name), "alice").unwrap().get_secret().is_ok());
+        assert!(matches!(legacy.get("alice", CredentialType::Secret), Err(crate::Error::EntryNotFound)));
+        assert!(legacy.exists("bob", CredentialType::Password).unwrap());
+        
+        let record = keyring.get_with_metadata("alice", CredentialType::Secret).unwrap();
+        assert_eq!(record.label.as_deref(), Some("Signing key"));
+        
+        // Running again finds nothing left to move
+        let rerun = keyring.migrate_naming(false).unwrap();
+        assert!(rerun.moved.is_empty());
+        assert_eq!(rerun.unchanged.len(), 2);
+        assert_eq!(rerun.conflicts, report.conflicts);
+    }
+    
+    #[test]
+    fn test_reserved_usernames_under_username_scheme() {
+        let service_name = setup_with_unique_service().unwrap();
+        let legacy = KeyringImplementation::new(&service_name, KeyringOptions::default());
+        legacy.set("alice", CredentialType::Password, CredentialValue::Password("legacy".to_string().into())).unwrap();
+        legacy.set_metadata("alice", CredentialType::Password, Some("Work".into()), None).unwrap();
+        let nested = format!("{}/bob", service_name);
+        legacy.set(&nested, CredentialType::Password, CredentialValue::Password("nested".to_string().into())).unwrap();
+        
+        let options = KeyringOptions { naming_scheme: NamingScheme::Username, ..KeyringOptions::default() };
+        let keyring = KeyringImplementation::new(&service_name, options);
+        let value = || CredentialValue::Password("overwrite".to_string().into());
+        
+        // The index, alice's metadata and alice's legacy entry respectively
+        for suffix in [".keyring-index", "alice/password.meta", "alice/password"] {
+            let username = format!("{}/{}", service_name, suffix);
+            assert!(matches!(keyring.set(&username, CredentialType::Password, value()), Err(crate::Error::InvalidInput(_))));
+        }
+        assert_eq!(legacy.list().unwrap().len(), 2);
+        assert_eq!(legacy.get_with_metadata("alice", CredentialType::Password).unwrap().label.as_deref(), Some("Work"));
+        match legacy.get("alice", CredentialType::Password).unwrap() {
+            CredentialValue::Password(password) => assert_eq!(&*password, "legacy"),
+            _ => panic!("Expected password"),
+        }
+        
+        // A legacy username that is reserved under the new scheme is left in place
+        let report = keyring.migrate_naming(false).unwrap();
+        assert_eq!(report.conflicts, vec![(nested.clone(), CredentialType::Password)]);
+        assert!(legacy.exists(&nested, CredentialType::Password).unwrap());
+    }
+    
+    #[test]
+    fn test_chunked_storage() {
+        let service_name = setup_with_unique_service().unwrap();
+        let options = KeyringOptions { chunk_size: Some(64), ..KeyringOptions::default() };
+        let keyring = KeyringImplementation::new(&service_name, options);
+        let chunk = |index: usize| Entry::new(&service_name, &format!("{}/bundle/secret.chunk{}", service_name, index)).unwrap();
+        
+        let bundle: Vec<u8> = (0..=255).cycle().take(1000).collect();
+        keyring.set("bundle", CredentialType::Secret, CredentialValue::Secret(bundle.clone().into())).unwrap();
+        let stored = Entry::new(&service_name, &format!("{}/bundle/secret", service_name)).unwrap().get_secret().unwrap();
+        assert!(stored.starts_with(b"KRS1m:"));
+        assert!(chunk(15).get_secret().is_ok());
+        assert!(matches!(chunk(16).get_secret(), Err(keyring_core::Error::NoEntry)));
+        match keyring.get("bundle", CredentialType::Secret).unwrap() {
+            CredentialValue::Secret(secret) => assert_eq!(&secret[..], &bundle[..]),
+            _ => panic!("Expected secret"),
+        }
+        
+        // A smaller value removes the chunks of the larger one, as recorded in its metadata
+        let metadata_entry = keyring.metadata_entry("bundle", &CredentialType::Secret).unwrap();
+        assert_eq!(metadata_entry.load().unwrap().unwrap().chunks, Some(16));
+        keyring.set("bundle", CredentialType::Secret, CredentialValue::Secret(vec![1, 2, 3].into())).unwrap();
+        assert!(matches!(chunk(0).get_secret(), Err(keyring_core::Error::NoEntry)));
+        assert!(matches!(chunk(15).get_secret(), Err(keyring_core::Error::NoEntry)));
+        assert_eq!(metadata_entry.load().unwrap().unwrap().chunks, Some(0));
+        
+        let password = "pässword ".repeat(20);
+        keyring.set("user", CredentialType::Password, CredentialValue::Password(password.clone().into())).unwrap();
+        let document = serde_json::json!({ "certificates": vec!["-----BEGIN CERTIFICATE-----"; 10] });
+        keyring.set_json("user", &document).unwrap();
+        match keyring.get("user", CredentialType::Password).unwrap() {
+            CredentialValue::Password(stored) => assert_eq!(&*stored, &password),
+            _ => panic!("Expected password"),
+        }
+        assert_eq!(keyring.get_json::<serde_json::Value>("user").unwrap(), document);
+        
+        keyring.delete("user", CredentialType::Json).unwrap();
+        let json_chunk = Entry::new(&service_name, &format!("{}/user/json.chunk0", service_name)).unwrap();
+        assert!(matches!(json_chunk.get_secret(), Err(keyring_core::Error::NoEntry)));
+        
+        // Without a chunk size, only values that look like a manifest are split
+        let unlimited = KeyringImplementation::new(&service_name, KeyringOptions::default());
+        let lookalike = "KRS1m:{\"chunks\":0}".to_string();
+        unlimited.set("lookalike", CredentialType::Password, CredentialValue::Password(lookalike.clone().into())).unwrap();
+        match unlimited.get("lookalike", CredentialType::Password).unwrap() {
+            CredentialValue::Password(stored) => assert_eq!(&*stored, &lookalike),
+            _ => panic!("Expected password"),
+        }
+        
+        let invalid = KeyringOptions { chunk_size: Some(0), ..KeyringOptions::default() };
+        assert!(matches!(invalid.validate(), Err(crate::Error::InvalidInput(_))));
+    }
+    
+    #[test]
+    fn test_undecodable_values_can_be_deleted() {
+        let service_name = setup_with_unique_service().unwrap();
+        let options = KeyringOptions { chunk_size: Some(64), ..KeyringOptions::default() };
+        let keyring = KeyringImplementation::new(&service_name, options);
+        let raw = |username: &str| Entry::new(&service_name, &format!("{}/{}", service_name, username)).unwrap();
+        
+        // A chunked value whose chunk was overwritten behind its back
+        keyring.set("bundle", CredentialType::Secret, CredentialValue::Secret(vec![7; 200].into())).unwrap();
+        raw("bundle/secret.chunk1").set_secret(b"tampered").unwrap();
+        assert!(matches!(keyring.get("bundle", CredentialType::Secret), Err(crate::Error::InvalidInput(_))));
+        assert!(keyring.exists("bundle", CredentialType::Secret).unwrap());
+        keyring.delete_user("bundle").unwrap();
+        assert!(!keyring.exists("bundle", CredentialType::Secret).unwrap());
+        assert!(matches!(raw("bundle/secret.chunk0").get_secret(), Err(keyring_core::Error::NoEntry)));
+        
+        // A secret in a format this build cannot read
+        keyring.set("alice", CredentialType::Secret, CredentialValue::Secret(vec![1].into())).unwrap();
+        keyring.set("bob", CredentialType::Secret, CredentialValue::Secret(vec![2].into())).unwrap();
+        raw("alice/secret").set_secret(b"KRS1R:not deflate").unwrap();
+        assert!(keyring.get("alice", CredentialType::Secret).is_err());
+        keyring.clear_service().unwrap();
+        assert!(keyring.list().unwrap().is_empty());
+        assert!(!keyring.exists("alice", CredentialType::Secret).unwrap());
+        assert!(!keyring.exists("bob", CredentialType::Secret).unwrap());
+    }
+    
+    #[test]
+    fn test_index_and_metadata_are_chunked() {
+        let service_name = setup_with_unique_service().unwrap();
+        let options = KeyringOptions { chunk_size: Some(64), ..KeyringOptions::default() };
+        let keyring = KeyringImplementation::new(&service_name, options);
+        let raw = |username: &str| Entry::new(&service_name, &format!("{}/{}", service_name, username)).unwrap();
+        
+        // Enough users that the index no longer fits in one entry
+        for index in 0..20 {
+            let username = format!("user{}@example.com", index);
+            keyring.set(&username, CredentialType::Password, CredentialValue::Password("pw".to_string().into())).unwrap();
+        }
+        assert!(raw(".keyring-index").get_password().unwrap().starts_with("KRS1m:"));
+        assert!(raw(".keyring-index.chunk1").get_secret().is_ok());
+        assert_eq!(keyring.list().unwrap().len(), 20);
+        
+        let comment = "A comment longer than one chunk. ".repeat(5);
+        keyring.set_metadata("user0@example.com", CredentialType::Password, Some("label".into()), Some(comment.clone())).unwrap();
+        assert!(raw("user0@example.com/password.meta").get_password().unwrap().starts_with("KRS1m:"));
+        let record = keyring.get_with_metadata("user0@example.com", CredentialType::Password).unwrap();
+        assert_eq!(record.comment, Some(comment));
+        
+        // Removing everything removes the chunks too
+        keyring.clear_service().unwrap();
+        assert!(keyring.list().unwrap().is_empty());
+        assert!(matches!(raw(".keyring-index.chunk0").get_secret(), Err(keyring_core::Error::NoEntry)));
+        assert!(matches!(raw("user0@example.com/password.meta.chunk0").get_secret(), Err(keyring_core::Error::NoEntry)));
+    }
+}
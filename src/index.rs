@@ -0,0 +1,103 @@
+use std::collections::BTreeSet;
+use std::sync::Mutex;
+
+use keyring_core::Entry;
+use zeroize::Zeroizing;
+
+use crate::chunks::{self, ChunkEntries};
+use crate::implementation::new_entry;
+use crate::models::{AccessPolicy, CredentialType};
+
+/// Username the index entry is stored under. Legacy credential entries always
+/// end in `/{type}`, and other naming schemes reject usernames that would name
+/// an entry under `{service}/`, so no caller-supplied username can produce this one.
+const INDEX_USERNAME: &str = ".keyring-index";
+
+/// Serializes read-modify-write cycles on index entries within the process
+static INDEX_LOCK: Mutex<()> = Mutex::new(());
+
+/// A plugin-maintained list of the entries stored for a service.
+///
+/// Most stores cannot enumerate entries, so listing and bulk operations read
+/// this index instead of searching. It is kept current by `set` and `delete`
+/// and can be rebuilt with [`KeyringImplementation::repair_index`](crate::implementation::KeyringImplementation::repair_index).
+///
+/// The index grows with every credential, so it is split across chunk entries
+/// like any other value larger than the configured chunk size.
+pub(crate) struct EntryIndex {
+    entry: Entry,
+    chunks: ChunkEntries,
+    chunk_size: Option<usize>,
+}
+
+impl EntryIndex {
+    /// `access_policy` protects the index the same way as the credentials it lists
+    pub(crate) fn new(service: &str, chunk_size: Option<usize>, access_policy: Option<AccessPolicy>) -> crate::Result<Self> {
+        let entry_username = format!("{}/{}", service, INDEX_USERNAME);
+        let entry = new_entry(service, &entry_username, access_policy)?;
+        Ok(Self { entry, chunks: ChunkEntries::new(service, &entry_username, access_policy), chunk_size })
+    }
+
+    /// Read the indexed entries; a missing index is an empty one
+    pub(crate) fn load(&self) -> crate::Result<BTreeSet<(String, CredentialType)>> {
+        self.read().map(|(entries, _)| entries)
+    }
+
+    /// The indexed entries and how many chunks they are split across
+    fn read(&self) -> crate::Result<(BTreeSet<(String, CredentialType)>, usize)> {
+        let stored = match self.entry.get_password() {
+            Ok(json) => Zeroizing::new(json.into_bytes()),
+            Err(keyring_core::Error::NoEntry) => return Ok((BTreeSet::new(), 0)),
+            Err(e) => return Err(e.into()),
+        };
+        let chunks = chunks::chunk_count(&stored).unwrap_or_default();
+        let json = self.chunks.load(stored)?;
+        let entries = serde_json::from_slice(&json)
+            .map_err(|e| crate::Error::InvalidInput(format!("Corrupt entry index: {}", e)))?;
+        Ok((entries, chunks))
+    }
+
+    pub(crate) fn insert(&self, username: &str, credential_type: &CredentialType) -> crate::Result<()> {
+        self.update(|entries| entries.insert((username.to_string(), credential_type.clone())))
+    }
+
+    pub(crate) fn remove(&self, username: &str, credential_type: &CredentialType) -> crate::Result<()> {
+        self.update(|entries| entries.remove(&(username.to_string(), credential_type.clone())))
+    }
+
+    /// Replace the whole index
+    pub(crate) fn replace(&self, entries: &BTreeSet<(String, CredentialType)>) -> crate::Result<()> {
+        let _guard = INDEX_LOCK.lock().unwrap();
+        self.write(entries, None)
+    }
+
+    /// Apply `change` and write the index back only if it reports a modification,
+    /// so recording an entry that is already listed costs a single read
+    fn update(&self, change: impl FnOnce(&mut BTreeSet<(String, CredentialType)>) -> bool) -> crate::Result<()> {
+        let _guard = INDEX_LOCK.lock().unwrap();
+        let (mut entries, chunks) = self.read()?;
+        if !change(&mut entries) {
+            return Ok(());
+        }
+        self.write(&entries, Some(chunks))
+    }
+
+    /// Persist `entries`, removing the index entry altogether when empty.
+    /// `previous_chunks` is how many chunks the index had, if known.
+    fn write(&self, entries: &BTreeSet<(String, CredentialType)>, previous_chunks: Option<usize>) -> crate::Result<()> {
+        if entries.is_empty() {
+            match self.entry.delete_credential() {
+                Ok(()) | Err(keyring_core::Error::NoEntry) => {},
+                Err(e) => return Err(e.into()),
+            }
+            return self.chunks.delete_from(0, previous_chunks);
+        }
+        let json = serde_json::to_string(entries)
+            .map_err(|e| crate::Error::InvalidInput(e.to_string()))?;
+        self.chunks.store(json.as_bytes(), self.chunk_size, previous_chunks, |stored| {
+            let stored = std::str::from_utf8(stored).map_err(|_| crate::Error::InvalidUtf8)?;
+            self.entry.set_password(stored).map_err(Into::into)
+        })?;
+        Ok(())
+    }
+}
@@ -0,0 +1,221 @@
+use std::time::Duration;
+
+use serde::{de::DeserializeOwned, Serialize};
+#[cfg(mobile)]
+use tauri::plugin::PluginHandle;
+use tauri::{AppHandle, Emitter, Runtime};
+
+use crate::crypto::SecretKey;
+use crate::implementation::{KeyringImplementation, KeyringOptions, ServiceName};
+use crate::models::*;
+use crate::scope::ServiceScope;
+use crate::service::ServiceHandle;
+
+/// Access to the keyring APIs.
+///
+/// Every operation acts on the current service, see [`Keyring::service_name`];
+/// use [`Keyring::service`] to act on another one.
+pub struct Keyring<R: Runtime> {
+    app: AppHandle<R>,
+    #[cfg(mobile)]
+    _handle: PluginHandle<R>,
+    service: ServiceName,
+    allowed_services: Vec<String>,
+    options: KeyringOptions,
+}
+
+impl<R: Runtime> Keyring<R> {
+    #[cfg(desktop)]
+    pub(crate) fn new(app: AppHandle<R>, service: String, allowed_services: Vec<String>, options: KeyringOptions) -> Self {
+        Self {
+            app,
+            service: ServiceName::new(service),
+            allowed_services,
+            options,
+        }
+    }
+
+    #[cfg(mobile)]
+    pub(crate) fn new(handle: PluginHandle<R>, service: String, allowed_services: Vec<String>, options: KeyringOptions) -> Self {
+        Self {
+            app: handle.app().clone(),
+            _handle: handle,
+            service: ServiceName::new(service),
+            allowed_services,
+            options,
+        }
+    }
+
+    pub(crate) fn implementation_for(&self, service: &str) -> KeyringImplementation {
+        KeyringImplementation::new(service, self.options.clone())
+    }
+
+    /// Emit [`CREDENTIAL_CHANGED_EVENT`] to every window. The username is only
+    /// sent hashed, since windows may only be allowed to see some users.
+    pub(crate) fn notify_changed(
+        &self,
+        service: &str,
+        operation: ChangeOperation,
+        username: Option<&str>,
+        credential_type: Option<CredentialType>,
+    ) {
+        let change = CredentialChange {
+            service: service.to_string(),
+            username: username.and_then(|username| self.options.redaction_key.hash(username)),
+            credential_type,
+            operation,
+        };
+        if let Err(e) = self.app.emit(CREDENTIAL_CHANGED_EVENT, change) {
+            keyring_log!(self, Warn, "Failed to emit credential change: {}", e);
+        }
+    }
+
+    /// The service name entries are currently stored under
+    pub fn service_name(&self) -> String {
+        self.service.get().to_string()
+    }
+
+    /// The credential store backend selected during setup
+    pub fn backend(&self) -> StoreBackend {
+        self.options.backend
+    }
+
+    /// Override the service name resolved from the plugin config.
+    /// Fails if it has already been overridden.
+    pub fn initialize_service(&self, service_name: String) -> crate::Result<()> {
+        keyring_log!(self, Info, "Initializing keyring service: {}", service_name);
+        self.service.initialize(service_name)
+    }
+
+    /// Scope operations to a separate named service
+    pub fn service(&self, name: &str) -> ServiceHandle<'_, R> {
+        ServiceHandle::new(self, name)
+    }
+
+    /// Resolve a service named by the frontend, which may only use the current
+    /// service and those allowed in the plugin config
+    pub(crate) fn frontend_service(&self, service: Option<&str>) -> crate::Result<ServiceHandle<'_, R>> {
+        let current = self.service_name();
+        match service {
+            Some(name) => {
+                ServiceScope::new(&current, &self.allowed_services).check(name)?;
+                Ok(self.service(name))
+            },
+            None => Ok(self.service(&current)),
+        }
+    }
+
+    /// The handle every operation below delegates to
+    fn current(&self) -> ServiceHandle<'_, R> {
+        self.service(&self.service_name())
+    }
+
+    pub fn set(&self, username: &str, credential_type: CredentialType, value: CredentialValue) -> crate::Result<()> {
+        self.current().set(username, credential_type, value)
+    }
+
+    /// Set a credential that reads as missing, and is removed, once `ttl` has elapsed
+    pub fn set_with_ttl(
+        &self,
+        username: &str,
+        credential_type: CredentialType,
+        value: CredentialValue,
+        ttl: Duration,
+    ) -> crate::Result<()> {
+        self.current().set_with_ttl(username, credential_type, value, ttl)
+    }
+
+    pub fn get(&self, username: &str, credential_type: CredentialType) -> crate::Result<CredentialValue> {
+        self.current().get(username, credential_type)
+    }
+
+    pub fn delete(&self, username: &str, credential_type: CredentialType) -> crate::Result<()> {
+        self.current().delete(username, credential_type)
+    }
+
+    pub fn exists(&self, username: &str, credential_type: CredentialType) -> crate::Result<bool> {
+        self.current().exists(username, credential_type)
+    }
+
+    /// Store `length` random bytes as the user's secret, generated in Rust
+    pub fn generate_secret(&self, username: &str, length: usize) -> crate::Result<()> {
+        self.current().generate_secret(username, length)
+    }
+
+    /// Store a random password following `policy` as the user's password, generated in Rust
+    pub fn generate_password(&self, username: &str, policy: &PasswordPolicy) -> crate::Result<()> {
+        self.current().generate_password(username, policy)
+    }
+
+    /// A handle for using the secret stored under `username` as a key
+    /// without reading it out
+    pub fn secret_key(&self, username: &str) -> SecretKey {
+        self.current().secret_key(username)
+    }
+
+    /// Get a credential along with its timestamps, label and comment
+    pub fn get_with_metadata(&self, username: &str, credential_type: CredentialType) -> crate::Result<CredentialRecord> {
+        self.current().get_with_metadata(username, credential_type)
+    }
+
+    /// Replace the label and comment of an existing credential
+    pub fn set_metadata(
+        &self,
+        username: &str,
+        credential_type: CredentialType,
+        label: Option<String>,
+        comment: Option<String>,
+    ) -> crate::Result<()> {
+        self.current().set_metadata(username, credential_type, label, comment)
+    }
+
+    /// Serialize `value` and store it as a JSON credential
+    pub fn set_json<T: Serialize>(&self, username: &str, value: &T) -> crate::Result<()> {
+        self.current().set_json(username, value)
+    }
+
+    /// Read a JSON credential into `T`, failing with [`Error::InvalidJson`](crate::Error::InvalidJson)
+    /// if the stored payload does not match
+    pub fn get_json<T: DeserializeOwned>(&self, username: &str) -> crate::Result<T> {
+        self.current().get_json(username)
+    }
+
+    /// Delete every credential type stored for a user
+    pub fn delete_user(&self, username: &str) -> crate::Result<()> {
+        self.current().delete_user(username)
+    }
+
+    /// Delete every credential stored for the current service
+    pub fn clear_service(&self) -> crate::Result<()> {
+        self.current().clear_service()
+    }
+
+    /// Delete every expired credential stored for the current service, returning how many were removed
+    pub fn purge_expired(&self) -> crate::Result<usize> {
+        self.current().purge_expired()
+    }
+
+    /// List the `(username, credential type)` pairs stored for the current service
+    pub fn list(&self) -> crate::Result<Vec<(String, CredentialType)>> {
+        self.current().list()
+    }
+
+    /// Move entries stored in the legacy layout to the configured naming scheme.
+    /// Safe to run repeatedly; with `dry_run` nothing is changed.
+    pub fn migrate_naming(&self, dry_run: bool) -> crate::Result<NamingMigration> {
+        self.current().migrate_naming(dry_run)
+    }
+
+    /// Rewrite secrets stored by earlier versions, or with another encoding, in the
+    /// configured format. Only listed entries are migrated, so on stores that cannot
+    /// search, repair the index first if it may be incomplete. Returns how many were rewritten.
+    pub fn migrate_encoding(&self) -> crate::Result<usize> {
+        self.current().migrate_encoding()
+    }
+
+    /// Rebuild the entry index used for listing by probing the store,
+    /// including every credential type of the given usernames
+    pub fn repair_index(&self, usernames: &[String]) -> crate::Result<Vec<(String, CredentialType)>> {
+        self.current().repair_index(usernames)
+    }
+}
@@ -1,26 +1,50 @@
 use tauri::{
-  plugin::{Builder, TauriPlugin},
+  plugin::{Builder as PluginBuilder, TauriPlugin},
   Manager, Runtime,
 };
+use log::LevelFilter;
+
+use crate::audit::{AuditCallback, AuditLog};
+use crate::redaction::RedactionKey;
 
 pub use models::*;
 
+#[macro_use]
+mod logging;
+
 #[cfg(desktop)]
 mod desktop;
 #[cfg(mobile)]
 mod mobile;
 
+mod audit;
+mod chunks;
 mod commands;
+mod config;
+mod crypto;
+mod encoding;
 mod error;
+mod keyring;
+#[cfg(all(desktop, feature = "encrypted-file"))]
+mod file_store;
+mod index;
+mod metadata;
+mod naming;
 mod models;
 mod implementation;
+mod random;
+mod redaction;
+mod scope;
+mod service;
 
+pub use audit::AuditEvent;
+pub use config::{AuditConfig, Config, EncryptedFileConfig, FileKeySource};
+pub use crypto::SecretKey;
 pub use error::{Error, Result};
+pub use service::ServiceHandle;
+pub use zeroize::Zeroizing;
 
-#[cfg(desktop)]
-use desktop::Keyring;
-#[cfg(mobile)]
-use mobile::Keyring;
+use keyring::Keyring;
 
 /// Extensions to [`tauri::App`], [`tauri::AppHandle`] and [`tauri::Window`] to access the keyring APIs.
 pub trait KeyringExt<R: Runtime> {
@@ -33,27 +57,228 @@ impl<R: Runtime, T: Manager<R>> crate::KeyringExt<R> for T {
   }
 }
 
-/// Initializes the plugin.
-pub fn init<R: Runtime>() -> TauriPlugin<R> {
-  Builder::new("keyring")
-    .invoke_handler(tauri::generate_handler![
-      commands::initialize_keyring,
-      commands::set_password,
-      commands::set_secret,
-      commands::get_password,
-      commands::get_secret,
-      commands::delete_password,
-      commands::delete_secret,
-      commands::has_password,
-      commands::has_secret
-    ])
-    .setup(|app, api| {
-      #[cfg(mobile)]
-      let keyring = mobile::init(app, api)?;
-      #[cfg(desktop)]
-      let keyring = desktop::init(app, api)?;
-      app.manage(keyring);
-      Ok(())
-    })
-    .build()
+/// Initializes the plugin with the default configuration.
+pub fn init<R: Runtime>() -> TauriPlugin<R, Option<Config>> {
+  Builder::new().build()
+}
+
+/// Builder for the keyring plugin.
+///
+/// Every value set here takes precedence over `plugins.keyring` in `tauri.conf.json`.
+///
+/// ```no_run
+/// use tauri_plugin_keyring::{SecretEncoding, StoreBackend};
+///
+/// tauri::Builder::default()
+///   .plugin(
+///     tauri_plugin_keyring::Builder::new()
+///       .service("com.example.myapp")
+///       .backend(StoreBackend::Native)
+///       .secret_encoding(SecretEncoding::Raw)
+///       .build(),
+///   );
+/// ```
+#[derive(Debug, Clone)]
+pub struct Builder {
+  config: Config,
+  log_level: LevelFilter,
+  audit_callback: Option<AuditCallback>,
+}
+
+impl Default for Builder {
+  fn default() -> Self {
+    Self {
+      config: Config::default(),
+      log_level: LevelFilter::Trace,
+      audit_callback: None,
+    }
+  }
+}
+
+impl Builder {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Service name entries are stored under. Defaults to the app identifier.
+  pub fn service(mut self, service: impl Into<String>) -> Self {
+    self.config.service = Some(service.into());
+    self
+  }
+
+  /// Other services the frontend may pass as a command's `service` argument.
+  ///
+  /// By default the frontend can only use the current service, so a webview
+  /// cannot reach entries other applications keep in the platform store.
+  /// Rust code can always use any service.
+  pub fn allowed_services(mut self, services: impl IntoIterator<Item = impl Into<String>>) -> Self {
+    self.config.allowed_services = Some(services.into_iter().map(Into::into).collect());
+    self
+  }
+
+  /// Credential store backend to register as the keyring default.
+  pub fn backend(mut self, backend: StoreBackend) -> Self {
+    self.config.backend = Some(backend);
+    self
+  }
+
+  /// Backends tried in order when the primary backend cannot be opened.
+  ///
+  /// On Linux this allows e.g. falling back from the Secret Service to keyutils
+  /// when no D-Bus session is available. Ignored on mobile.
+  pub fn fallback_backends(mut self, backends: impl IntoIterator<Item = StoreBackend>) -> Self {
+    self.config.fallback_backends = Some(backends.into_iter().collect());
+    self
+  }
+
+  /// File the `EncryptedFile` backend stores entries in, relative to the app data dir.
+  pub fn encrypted_file_path(mut self, path: impl Into<std::path::PathBuf>) -> Self {
+    self.config.encrypted_file.get_or_insert_with(Default::default).path = Some(path.into());
+    self
+  }
+
+  /// Where the `EncryptedFile` backend gets its encryption key.
+  pub fn encrypted_file_key(mut self, source: FileKeySource) -> Self {
+    self.config.encrypted_file.get_or_insert_with(Default::default).key_source = Some(source);
+    self
+  }
+
+  /// Store every credential in the encrypted file, under a master key kept in
+  /// the platform credential store. Requires the `encrypted-file` feature.
+  ///
+  /// Desktop only: on mobile, plugin setup fails with this backend.
+  pub fn vault(mut self) -> Self {
+    self.config.backend = Some(StoreBackend::EncryptedFile);
+    self.config.encrypted_file.get_or_insert_with(Default::default).key_source = Some(FileKeySource::Keyring);
+    self
+  }
+
+  /// Most verbose level the plugin logs at.
+  pub fn log_level(mut self, level: LevelFilter) -> Self {
+    self.log_level = level;
+    self
+  }
+
+  /// How usernames appear in log output. Defaults to [`LogRedaction::Hash`].
+  pub fn log_redaction(mut self, redaction: LogRedaction) -> Self {
+    self.config.log_redaction = Some(redaction);
+    self
+  }
+
+  /// How entries are named in the store. Existing entries are moved to a new
+  /// scheme with `migrate_naming`.
+  pub fn naming_scheme(mut self, scheme: NamingScheme) -> Self {
+    self.config.naming_scheme = Some(scheme);
+    self
+  }
+
+  /// Split values larger than `bytes` across several entries, for stores that
+  /// limit the size of one. Defaults to 1024 for the Windows Credential Manager;
+  /// chunks are never larger than 1 MiB.
+  pub fn chunk_size(mut self, bytes: usize) -> Self {
+    self.config.chunk_size = Some(bytes);
+    self
+  }
+
+  /// Record an audit event for every command, keeping recent events in memory.
+  pub fn audit(mut self) -> Self {
+    self.config.audit.get_or_insert_with(Default::default);
+    self
+  }
+
+  /// Enable auditing and append events to this file as JSON Lines, relative to the app log dir.
+  pub fn audit_file(mut self, path: impl Into<std::path::PathBuf>) -> Self {
+    self.config.audit.get_or_insert_with(Default::default).path = Some(path.into());
+    self
+  }
+
+  /// Enable auditing and pass every event to `callback` as it is recorded.
+  pub fn audit_callback(mut self, callback: impl Fn(&AuditEvent) + Send + Sync + 'static) -> Self {
+    self.config.audit.get_or_insert_with(Default::default);
+    self.audit_callback = Some(AuditCallback(std::sync::Arc::new(callback)));
+    self
+  }
+
+  /// Keychain access policy for new entries. Only honoured on iOS.
+  pub fn access_policy(mut self, policy: AccessPolicy) -> Self {
+    self.config.access_policy = Some(policy);
+    self
+  }
+
+  /// Encoding used when writing secrets.
+  pub fn secret_encoding(mut self, encoding: SecretEncoding) -> Self {
+    self.config.secret_encoding = Some(encoding);
+    self
+  }
+
+  /// Compression applied to secrets before they are encoded. Secrets are only
+  /// stored compressed when that makes them smaller.
+  pub fn secret_compression(mut self, compression: SecretCompression) -> Self {
+    self.config.secret_compression = Some(compression);
+    self
+  }
+
+  pub fn build<R: Runtime>(self) -> TauriPlugin<R, Option<Config>> {
+    PluginBuilder::<R, Option<Config>>::new("keyring")
+      .invoke_handler(tauri::generate_handler![
+        commands::initialize_keyring,
+        commands::set_password,
+        commands::set_secret,
+        commands::get_password,
+        commands::get_secret,
+        commands::delete_password,
+        commands::delete_secret,
+        commands::has_password,
+        commands::has_secret,
+        commands::set_json,
+        commands::get_json,
+        commands::delete_json,
+        commands::has_json,
+        commands::get_with_metadata,
+        commands::set_metadata,
+        commands::delete_user,
+        commands::clear_service,
+        commands::purge_expired,
+        commands::list_credentials,
+        commands::generate_secret,
+        commands::generate_password,
+        commands::hmac_sha256,
+        commands::ed25519_sign,
+        commands::ed25519_public_key,
+        commands::aes_gcm_encrypt,
+        commands::aes_gcm_decrypt,
+        commands::get_audit_events,
+        commands::migrate_naming
+      ])
+      .setup(move |app, api| {
+        let config = self.config.or(api.config().clone().unwrap_or_default());
+        // Keys the username hashes in log output and audit events; only
+        // created once a username is first hashed
+        let redaction_key = RedactionKey::at(
+          app.path().app_data_dir().ok().map(|dir| dir.join("keyring-redaction.key")),
+          self.log_level,
+        );
+        app.manage(redaction_key);
+        if let Some(audit) = &config.audit {
+          let path = match &audit.path {
+            Some(path) => Some(
+              app
+                .path()
+                .app_log_dir()
+                .map_err(|e| Error::PlatformError(e.to_string()))?
+                .join(path),
+            ),
+            None => None,
+          };
+          app.manage(AuditLog::new(path.as_deref(), self.audit_callback, self.log_level)?);
+        }
+        #[cfg(mobile)]
+        let keyring = mobile::init(app, api, config, self.log_level)?;
+        #[cfg(desktop)]
+        let keyring = desktop::init(app, api, config, self.log_level)?;
+        app.manage(keyring);
+        Ok(())
+      })
+      .build()
+  }
 }
@@ -0,0 +1,16 @@
+//! The plugin's log output, capped at the level configured with `log_level`.
+
+/// Log through the `log` facade if the level is within the configured verbosity.
+///
+/// `keyring_log!(self, Info, ...)` reads the level from `self.options`, and
+/// `keyring_log!(level = log_level, Info, ...)` takes it directly.
+macro_rules! keyring_log {
+    (level = $log_level:expr, $level:ident, $($arg:tt)+) => {
+        if log::Level::$level <= $log_level {
+            log::log!(log::Level::$level, $($arg)+);
+        }
+    };
+    ($self:ident, $level:ident, $($arg:tt)+) => {
+        keyring_log!(level = $self.options.log_level, $level, $($arg)+)
+    };
+}
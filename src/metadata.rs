@@ -0,0 +1,103 @@
+use std::cell::Cell;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use keyring_core::Entry;
+use serde::{Deserialize, Serialize};
+use zeroize::Zeroizing;
+
+use crate::chunks::{self, ChunkEntries};
+use crate::implementation::new_entry;
+use crate::models::AccessPolicy;
+
+/// Suffix of the companion entry holding a credential's metadata. Legacy
+/// credential entries always end in a bare credential type, and other naming
+/// schemes keep credentials out of `{service}/`, so the two cannot collide.
+const METADATA_SUFFIX: &str = ".meta";
+
+/// Bookkeeping stored next to a credential
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub(crate) struct Metadata {
+    pub created_at: Option<u64>,
+    pub updated_at: Option<u64>,
+    pub label: Option<String>,
+    pub comment: Option<String>,
+    pub expires_at: Option<u64>,
+    /// How many chunks the credential's value is split across, so rewriting it
+    /// need not probe the store for chunks left over from a larger value
+    pub chunks: Option<usize>,
+}
+
+impl Metadata {
+    pub(crate) fn is_expired(&self, now: u64) -> bool {
+        self.expires_at.is_some_and(|expires_at| expires_at <= now)
+    }
+}
+
+/// The companion entry a credential's [`Metadata`] is stored in
+///
+/// Labels and comments have no length limit, so the metadata is split across
+/// chunk entries like a credential value when it is too large for one entry.
+pub(crate) struct MetadataEntry {
+    entry: Entry,
+    chunks: ChunkEntries,
+    chunk_size: Option<usize>,
+    /// How many chunks the stored metadata is split across, once read or written
+    stored_chunks: Cell<Option<usize>>,
+}
+
+impl MetadataEntry {
+    /// `entry_username` is the full `service/username/type` of the credential
+    /// `access_policy` protects the metadata the same way as the credential
+    pub(crate) fn new(service: &str, entry_username: &str, chunk_size: Option<usize>, access_policy: Option<AccessPolicy>) -> crate::Result<Self> {
+        let metadata_username = format!("{}{}", entry_username, METADATA_SUFFIX);
+        let entry = new_entry(service, &metadata_username, access_policy)?;
+        Ok(Self {
+            entry,
+            chunks: ChunkEntries::new(service, &metadata_username, access_policy),
+            chunk_size,
+            stored_chunks: Cell::new(None),
+        })
+    }
+
+    /// Read the metadata; credentials written without any have none
+    pub(crate) fn load(&self) -> crate::Result<Option<Metadata>> {
+        let stored = match self.entry.get_password() {
+            Ok(json) => Zeroizing::new(json.into_bytes()),
+            Err(keyring_core::Error::NoEntry) => {
+                self.stored_chunks.set(Some(0));
+                return Ok(None);
+            },
+            Err(e) => return Err(e.into()),
+        };
+        self.stored_chunks.set(chunks::chunk_count(&stored));
+        Ok(Some(serde_json::from_slice(&self.chunks.load(stored)?)?))
+    }
+
+    /// Write the metadata. Leftover chunks are only probed for if it was not
+    /// loaded through this entry first.
+    pub(crate) fn store(&self, metadata: &Metadata) -> crate::Result<()> {
+        let json = serde_json::to_string(metadata)?;
+        let chunks = self.chunks.store(json.as_bytes(), self.chunk_size, self.stored_chunks.get(), |stored| {
+            let stored = std::str::from_utf8(stored).map_err(|_| crate::Error::InvalidUtf8)?;
+            self.entry.set_password(stored).map_err(Into::into)
+        })?;
+        self.stored_chunks.set(Some(chunks));
+        Ok(())
+    }
+
+    pub(crate) fn delete(&self) -> crate::Result<()> {
+        match self.entry.delete_credential() {
+            Ok(()) | Err(keyring_core::Error::NoEntry) => {},
+            Err(e) => return Err(e.into()),
+        }
+        self.chunks.delete_from(0, self.stored_chunks.get())
+    }
+}
+
+/// Seconds since the Unix epoch
+pub(crate) fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
@@ -1,75 +1,62 @@
-use serde::de::DeserializeOwned;
-use tauri::{
-    plugin::{PluginApi, PluginHandle},
-    AppHandle, Runtime,
-};
+use log::LevelFilter;
+use tauri::{plugin::PluginApi, AppHandle, Manager, Runtime};
 
-use crate::implementation::KeyringImplementation;
+use crate::config::Config;
+use crate::keyring::Keyring;
 use crate::models::*;
+use crate::redaction::RedactionKey;
 
 #[cfg(target_os = "ios")]
 tauri::ios_plugin_binding!(init_plugin_keyring);
 
 // initializes the Kotlin or Swift plugin classes
-pub fn init<R: Runtime, C: DeserializeOwned>(
-    _app: &AppHandle<R>,
-    api: PluginApi<R, C>,
+pub fn init<R: Runtime>(
+    app: &AppHandle<R>,
+    api: PluginApi<R, Option<Config>>,
+    config: Config,
+    log_level: LevelFilter,
 ) -> crate::Result<Keyring<R>> {
+    let service = config.service_name(app);
+    let backend = match config.backend.unwrap_or_default() {
+        StoreBackend::Mock => StoreBackend::Mock,
+        StoreBackend::EncryptedFile => {
+            return Err(crate::Error::InvalidInput(
+                "The encrypted file backend is not available on mobile".into(),
+            ))
+        }
+        // Each platform has a single native store
+        _ => StoreBackend::Native,
+    };
+    let options = config.keyring_options(log_level, backend, app.state::<RedactionKey>().inner().clone())?;
+    let allowed_services = config.allowed_services.clone().unwrap_or_default();
+    let use_mock = backend == StoreBackend::Mock;
+    if use_mock {
+        use keyring_core::mock::Store;
+        let store = Store::new().map_err(|e| crate::Error::PlatformError(e.to_string()))?;
+        keyring_core::set_default_store(store);
+    }
+
     #[cfg(target_os = "android")]
     {
-        use android_native_keyring_store::AndroidStore;
-        let store = AndroidStore::from_ndk_context()
-            .map_err(|e| crate::Error::PlatformError(e.to_string()))?;
-        keyring_core::set_default_store(store);
+        if !use_mock {
+            use android_native_keyring_store::AndroidStore;
+            let store = AndroidStore::from_ndk_context()
+                .map_err(|e| crate::Error::PlatformError(e.to_string()))?;
+            keyring_core::set_default_store(store);
+        }
         let handle =
             api.register_android_plugin("com.alaydriem.bvc.plugin.keyring", "KeyringPlugin")?;
-        Ok(Keyring(handle))
+        Ok(Keyring::new(handle, service, allowed_services, options))
     }
 
     #[cfg(target_os = "ios")]
     {
-        use apple_native_keyring_store::protected::Store as IOSStore;
-        let store = IOSStore::new().map_err(|e| crate::Error::PlatformError(e.to_string()))?;
-        keyring_core::set_default_store(store);
+        if !use_mock {
+            use apple_native_keyring_store::protected::Store as IOSStore;
+            let store = IOSStore::new().map_err(|e| crate::Error::PlatformError(e.to_string()))?;
+            keyring_core::set_default_store(store);
+        }
         let handle = api.register_ios_plugin(init_plugin_keyring)?;
-        Ok(Keyring(handle))
-    }
-}
-
-/// Access to the keyring APIs.
-pub struct Keyring<R: Runtime>(PluginHandle<R>);
-
-impl<R: Runtime> Keyring<R> {
-    fn implementation(&self) -> KeyringImplementation {
-        KeyringImplementation
-    }
-
-    pub fn initialize_service(&self, service_name: String) -> crate::Result<()> {
-        KeyringImplementation::initialize_service(service_name)
-    }
-
-    pub fn set(
-        &self,
-        username: &str,
-        credential_type: CredentialType,
-        value: CredentialValue,
-    ) -> crate::Result<()> {
-        self.implementation().set(username, credential_type, value)
-    }
-
-    pub fn get(
-        &self,
-        username: &str,
-        credential_type: CredentialType,
-    ) -> crate::Result<CredentialValue> {
-        self.implementation().get(username, credential_type)
-    }
-
-    pub fn delete(&self, username: &str, credential_type: CredentialType) -> crate::Result<()> {
-        self.implementation().delete(username, credential_type)
-    }
-
-    pub fn exists(&self, username: &str, credential_type: CredentialType) -> crate::Result<bool> {
-        self.implementation().exists(username, credential_type)
+        Ok(Keyring::new(handle, service, allowed_services, options))
     }
 }
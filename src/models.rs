@@ -1,9 +1,16 @@
 use serde::{Deserialize, Serialize};
+use zeroize::{Zeroize, Zeroizing};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
 pub enum CredentialType {
     Password, // UTF-8 strings
     Secret,   // Binary data (Vec<u8>)
+    Json,     // Structured data (serde_json::Value)
+}
+
+impl CredentialType {
+    /// Every credential type, in the order entries are probed
+    pub const ALL: &'static [CredentialType] = &[CredentialType::Password, CredentialType::Secret, CredentialType::Json];
 }
 
 impl std::fmt::Display for CredentialType {
@@ -11,14 +18,204 @@ impl std::fmt::Display for CredentialType {
         match self {
             CredentialType::Password => write!(f, "password"),
             CredentialType::Secret => write!(f, "secret"),
+            CredentialType::Json => write!(f, "json"),
+        }
+    }
+}
+
+impl std::str::FromStr for CredentialType {
+    type Err = crate::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "password" => Ok(CredentialType::Password),
+            "secret" => Ok(CredentialType::Secret),
+            "json" => Ok(CredentialType::Json),
+            _ => Err(crate::Error::InvalidInput(format!("Unknown credential type: {}", s))),
         }
     }
 }
 
-// Simple result wrapper that can hold either type
+// Simple result wrapper that can hold either type.
+// Passwords and secrets are wiped from memory when dropped.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type", content = "data")]
 pub enum CredentialValue {
-    Password(String),
-    Secret(Vec<u8>),
+    Password(Zeroizing<String>),
+    Secret(Zeroizing<Vec<u8>>),
+    Json(serde_json::Value),
+}
+
+impl Zeroize for CredentialValue {
+    fn zeroize(&mut self) {
+        match self {
+            CredentialValue::Password(password) => password.zeroize(),
+            CredentialValue::Secret(secret) => secret.zeroize(),
+            // `Value` cannot be wiped in place, so drop its contents instead
+            CredentialValue::Json(value) => *value = serde_json::Value::Null,
+        }
+    }
+}
+
+/// A credential together with its metadata.
+///
+/// Timestamps are seconds since the Unix epoch. They are `None` for entries
+/// written before metadata was recorded or by other tools, and `expires_at`
+/// is `None` for credentials that do not expire.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CredentialRecord {
+    pub value: CredentialValue,
+    pub created_at: Option<u64>,
+    pub updated_at: Option<u64>,
+    pub label: Option<String>,
+    pub comment: Option<String>,
+    pub expires_at: Option<u64>,
+}
+
+/// How credential entries are named in the store.
+///
+/// Entries are identified by a service and a user; keychain UIs show both.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum NamingScheme {
+    /// Service `{service}`, user `{service}/{username}/{type}`
+    #[default]
+    Legacy,
+    /// User `{username}`; passwords under service `{service}` and other
+    /// types under `{service}.secret` and `{service}.json`
+    Username,
+    /// Service `{service}`, user rendered from a template that contains
+    /// `{username}` and `{type}` once each and may contain `{service}`
+    Template(String),
+}
+
+/// Outcome of moving entries to the configured [`NamingScheme`].
+///
+/// Entries are listed as `(username, credential type)` pairs.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NamingMigration {
+    /// Nothing was changed; the lists describe what would happen
+    pub dry_run: bool,
+    /// Entries moved from the legacy layout
+    pub moved: Vec<(String, CredentialType)>,
+    /// Entries with nothing left to move
+    pub unchanged: Vec<(String, CredentialType)>,
+    /// Entries present in both layouts with different values, or whose username
+    /// is reserved under the new scheme, left untouched
+    pub conflicts: Vec<(String, CredentialType)>,
+}
+
+/// Event emitted to every window when a credential is set or deleted
+pub const CREDENTIAL_CHANGED_EVENT: &str = "keyring://changed";
+
+/// What happened to the credentials in a [`CredentialChange`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ChangeOperation {
+    Set,
+    Delete,
+}
+
+/// Payload of [`CREDENTIAL_CHANGED_EVENT`].
+///
+/// The event reaches every window whatever its username scope, so `username`
+/// is the keyed hash also used in log output and audit events, never the name
+/// itself. It is `None` when every user's credentials changed, e.g. after
+/// `clear_service`, or when the hashing key is unavailable, and `credential_type`
+/// is `None` when several types changed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CredentialChange {
+    pub service: String,
+    pub username: Option<String>,
+    pub credential_type: Option<CredentialType>,
+    pub operation: ChangeOperation,
+}
+
+/// Length and character classes of a generated password.
+///
+/// Every enabled class is guaranteed to appear at least once.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct PasswordPolicy {
+    pub length: usize,
+    pub lowercase: bool,
+    pub uppercase: bool,
+    pub digits: bool,
+    pub symbols: bool,
+}
+
+impl Default for PasswordPolicy {
+    fn default() -> Self {
+        Self {
+            length: 24,
+            lowercase: true,
+            uppercase: true,
+            digits: true,
+            symbols: true,
+        }
+    }
+}
+
+/// Credential store backend the plugin registers as the keyring default
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum StoreBackend {
+    #[default]
+    Native,        // Platform keychain / credential manager
+    Mock,          // In-memory store, useful for tests
+    SecretService, // Linux Secret Service over D-Bus
+    Keyutils,      // Linux kernel keyutils
+    EncryptedFile, // Encrypted file in the app data dir
+}
+
+/// When a stored item may be read. Only honoured by the iOS keychain store.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum AccessPolicy {
+    AfterFirstUnlock,
+    WhenUnlocked,
+    RequireUserPresence,
+}
+
+impl AccessPolicy {
+    /// The `access-policy` modifier value understood by the store
+    pub(crate) fn as_modifier(&self) -> &'static str {
+        match self {
+            AccessPolicy::AfterFirstUnlock => "after-first-unlock",
+            AccessPolicy::WhenUnlocked => "when-unlocked",
+            AccessPolicy::RequireUserPresence => "require-user-presence",
+        }
+    }
+}
+
+/// How secrets are written to the store. Either can be read back regardless of the setting.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SecretEncoding {
+    Base64, // Text-safe, for stores that mangle binary data
+    #[default]
+    Raw,    // Bytes stored as-is
+}
+
+/// Whether secrets are compressed before being encoded. Compressed and
+/// uncompressed secrets can be read back regardless of the setting.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SecretCompression {
+    #[default]
+    None,
+    Deflate, // Requires the `compression` feature
+}
+
+/// How account identifiers appear in the plugin's log output
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum LogRedaction {
+    None, // Logged verbatim
+    #[default]
+    Hash, // Replaced by a short stable hash, so lines can still be correlated
+    Omit, // Replaced by a placeholder
 }
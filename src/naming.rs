@@ -0,0 +1,210 @@
+//! Where credential entries live in the store under each [`NamingScheme`].
+//!
+//! Only credential entries follow the scheme. Metadata, chunks and the entry
+//! index keep their `{service}/...` names, so migrating between schemes never
+//! moves them. Other schemes therefore reserve that prefix: a username that
+//! would place a credential under it is rejected, and so is a template that
+//! always would.
+//!
+//! The username scheme also places credentials in the services `{service}.secret`
+//! and `{service}.json`, so service names ending in those suffixes are rejected
+//! under it.
+
+use crate::models::{CredentialType, NamingScheme};
+
+/// The `(service, user)` pair identifying a store entry
+pub(crate) type Location = (String, String);
+
+const USERNAME_PLACEHOLDER: &str = "{username}";
+const TYPE_PLACEHOLDER: &str = "{type}";
+const SERVICE_PLACEHOLDER: &str = "{service}";
+
+impl NamingScheme {
+    /// Check that every credential gets its own entry under this scheme
+    pub(crate) fn validate(&self) -> crate::Result<()> {
+        if let NamingScheme::Template(template) = self {
+            if template.matches(USERNAME_PLACEHOLDER).count() != 1 || template.matches(TYPE_PLACEHOLDER).count() != 1 {
+                return Err(crate::Error::InvalidInput(format!(
+                    "Naming template must contain {} and {} exactly once",
+                    USERNAME_PLACEHOLDER, TYPE_PLACEHOLDER
+                )));
+            }
+            if template.starts_with(&format!("{}/", SERVICE_PLACEHOLDER)) {
+                return Err(crate::Error::InvalidInput(format!(
+                    "Naming template must not start with \"{}/\", which is reserved for internal entries",
+                    SERVICE_PLACEHOLDER
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// The entry a credential is stored in
+    pub(crate) fn locate(&self, service: &str, username: &str, credential_type: &CredentialType) -> crate::Result<Location> {
+        let (entry_service, entry_user) = match self {
+            NamingScheme::Legacy => return Ok((service.to_string(), format!("{}/{}/{}", service, username, credential_type))),
+            NamingScheme::Username => {
+                // Would share an entry with another service's typed credentials
+                if let Some(credential_type) = CredentialType::ALL
+                    .iter()
+                    .find(|t| **t != CredentialType::Password && service.ends_with(&format!(".{}", t)))
+                {
+                    return Err(crate::Error::InvalidInput(format!(
+                        "Service names ending in \".{}\" are reserved under the username naming scheme",
+                        credential_type
+                    )));
+                }
+                (typed_service(service, credential_type), username.to_string())
+            },
+            NamingScheme::Template(template) => {
+                self.validate()?;
+                let user = template
+                    .replace(SERVICE_PLACEHOLDER, service)
+                    .replace(TYPE_PLACEHOLDER, &credential_type.to_string())
+                    .replace(USERNAME_PLACEHOLDER, username);
+                (service.to_string(), user)
+            },
+        };
+        // Would overwrite metadata, chunks, the index or a legacy credential
+        if entry_user.starts_with(&format!("{}/", service)) {
+            return Err(crate::Error::InvalidInput(format!(
+                "Usernames naming an entry under \"{}/\" are reserved under this naming scheme",
+                service
+            )));
+        }
+        Ok((entry_service, entry_user))
+    }
+
+    /// Every store service this scheme places credentials of `service` in
+    pub(crate) fn services(&self, service: &str) -> Vec<String> {
+        match self {
+            NamingScheme::Username => CredentialType::ALL.iter().map(|t| typed_service(service, t)).collect(),
+            _ => vec![service.to_string()],
+        }
+    }
+
+    /// The credential an entry found in the store holds, if it is one under this scheme
+    pub(crate) fn parse(&self, service: &str, entry_service: &str, entry_user: &str) -> Option<(String, CredentialType)> {
+        // Metadata, the index and legacy credentials live under this prefix
+        let internal_prefix = format!("{}/", service);
+        match self {
+            NamingScheme::Legacy => {
+                if entry_service != service {
+                    return None;
+                }
+                let (username, credential_type) = entry_user.strip_prefix(&internal_prefix)?.rsplit_once('/')?;
+                Some((username.to_string(), credential_type.parse().ok()?))
+            },
+            NamingScheme::Username => {
+                if entry_user.starts_with(&internal_prefix) {
+                    return None;
+                }
+                let credential_type = CredentialType::ALL.iter().find(|t| typed_service(service, t) == entry_service)?;
+                Some((entry_user.to_string(), credential_type.clone()))
+            },
+            NamingScheme::Template(template) => {
+                if entry_service != service || self.validate().is_err() {
+                    return None;
+                }
+                let template = template.replace(SERVICE_PLACEHOLDER, service);
+                CredentialType::ALL.iter().find_map(|credential_type| {
+                    let pattern = template.replace(TYPE_PLACEHOLDER, &credential_type.to_string());
+                    let (prefix, suffix) = pattern.split_once(USERNAME_PLACEHOLDER)?;
+                    let username = entry_user.strip_prefix(prefix)?.strip_suffix(suffix)?;
+                    Some((username.to_string(), credential_type.clone()))
+                })
+            },
+        }
+    }
+}
+
+/// Passwords keep the plain service name; other types get their own
+fn typed_service(service: &str, credential_type: &CredentialType) -> String {
+    match credential_type {
+        CredentialType::Password => service.to_string(),
+        other => format!("{}.{}", service, other),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SERVICE: &str = "com.example.app";
+
+    fn round_trip(scheme: &NamingScheme, username: &str, credential_type: CredentialType) -> Location {
+        let (entry_service, entry_user) = scheme.locate(SERVICE, username, &credential_type).unwrap();
+        assert_eq!(
+            scheme.parse(SERVICE, &entry_service, &entry_user),
+            Some((username.to_string(), credential_type))
+        );
+        (entry_service, entry_user)
+    }
+
+    #[test]
+    fn test_legacy_scheme() {
+        let scheme = NamingScheme::Legacy;
+        assert_eq!(
+            round_trip(&scheme, "alice", CredentialType::Secret),
+            (SERVICE.to_string(), "com.example.app/alice/secret".to_string())
+        );
+        round_trip(&scheme, "a/b", CredentialType::Json);
+        assert_eq!(scheme.parse(SERVICE, SERVICE, "com.example.app/.keyring-index"), None);
+        assert_eq!(scheme.parse(SERVICE, SERVICE, "com.example.app/alice/secret.meta"), None);
+    }
+
+    #[test]
+    fn test_username_scheme() {
+        let scheme = NamingScheme::Username;
+        assert_eq!(
+            round_trip(&scheme, "alice", CredentialType::Password),
+            (SERVICE.to_string(), "alice".to_string())
+        );
+        assert_eq!(
+            round_trip(&scheme, "alice", CredentialType::Json),
+            ("com.example.app.json".to_string(), "alice".to_string())
+        );
+        assert_eq!(scheme.services(SERVICE).len(), 3);
+        // Internal and legacy entries are not credentials under this scheme
+        assert_eq!(scheme.parse(SERVICE, SERVICE, "com.example.app/alice/password"), None);
+        assert_eq!(scheme.parse(SERVICE, "other.service", "alice"), None);
+
+        // Usernames that would land on an internal or legacy entry
+        for reserved in ["com.example.app/.keyring-index", "com.example.app/alice/password.meta", "com.example.app/alice/password"] {
+            for credential_type in CredentialType::ALL {
+                assert!(matches!(scheme.locate(SERVICE, reserved, credential_type), Err(crate::Error::InvalidInput(_))));
+            }
+        }
+
+        // Services that would share entries with another service's secrets or JSON
+        for reserved in ["com.example.app.secret", "com.example.app.json"] {
+            for credential_type in CredentialType::ALL {
+                assert!(matches!(scheme.locate(reserved, "alice", credential_type), Err(crate::Error::InvalidInput(_))));
+            }
+        }
+        round_trip(&scheme, "alice", CredentialType::Secret);
+        assert!(scheme.locate("com.example.app.password", "alice", &CredentialType::Secret).is_ok());
+        assert!(NamingScheme::Legacy.locate("com.example.app.secret", "alice", &CredentialType::Password).is_ok());
+    }
+
+    #[test]
+    fn test_template_scheme() {
+        let scheme = NamingScheme::Template("{username} ({type})".to_string());
+        assert_eq!(
+            round_trip(&scheme, "alice", CredentialType::Secret),
+            (SERVICE.to_string(), "alice (secret)".to_string())
+        );
+        round_trip(&scheme, "bob (password)", CredentialType::Json);
+        assert_eq!(scheme.parse(SERVICE, SERVICE, "alice"), None);
+
+        let with_service = NamingScheme::Template("{service}:{type}:{username}".to_string());
+        assert_eq!(round_trip(&with_service, "carol", CredentialType::Password).1, "com.example.app:password:carol");
+
+        // Every credential would land under the reserved `{service}/` prefix
+        for invalid in ["{username}", "{type}/{type}/{username}", "{service}/{username}", "{service}/{username}.{type}"] {
+            let scheme = NamingScheme::Template(invalid.to_string());
+            assert!(matches!(scheme.validate(), Err(crate::Error::InvalidInput(_))));
+            assert!(scheme.locate(SERVICE, "alice", &CredentialType::Password).is_err());
+        }
+    }
+}
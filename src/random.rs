@@ -0,0 +1,116 @@
+//! Random secrets and passwords generated from the operating system's RNG.
+
+use rand_core::{OsRng, RngCore};
+use zeroize::Zeroizing;
+
+use crate::models::PasswordPolicy;
+
+/// Upper bound on generated secrets, well above any key size
+pub(crate) const MAX_SECRET_LENGTH: usize = 4096;
+
+/// Upper bound on generated passwords
+pub(crate) const MAX_PASSWORD_LENGTH: usize = 1024;
+
+const LOWERCASE: &[u8] = b"abcdefghijklmnopqrstuvwxyz";
+const UPPERCASE: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+const DIGITS: &[u8] = b"0123456789";
+const SYMBOLS: &[u8] = b"!#$%&()*+,-./:;<=>?@[]^_{|}~";
+
+/// `length` random bytes
+pub(crate) fn secret(length: usize) -> crate::Result<Zeroizing<Vec<u8>>> {
+    if length == 0 || length > MAX_SECRET_LENGTH {
+        return Err(crate::Error::InvalidInput(format!(
+            "Secret length must be between 1 and {} bytes",
+            MAX_SECRET_LENGTH
+        )));
+    }
+    let mut bytes = Zeroizing::new(vec![0u8; length]);
+    OsRng.fill_bytes(&mut bytes);
+    Ok(bytes)
+}
+
+/// A password following `policy`
+pub(crate) fn password(policy: &PasswordPolicy) -> crate::Result<Zeroizing<String>> {
+    let classes: Vec<&[u8]> = [
+        (policy.lowercase, LOWERCASE),
+        (policy.uppercase, UPPERCASE),
+        (policy.digits, DIGITS),
+        (policy.symbols, SYMBOLS),
+    ]
+    .into_iter()
+    .filter(|(enabled, _)| *enabled)
+    .map(|(_, class)| class)
+    .collect();
+
+    if classes.is_empty() {
+        return Err(crate::Error::InvalidInput("Password policy enables no character classes".into()));
+    }
+    if policy.length < classes.len() || policy.length > MAX_PASSWORD_LENGTH {
+        return Err(crate::Error::InvalidInput(format!(
+            "Password length must be between {} and {}",
+            classes.len(),
+            MAX_PASSWORD_LENGTH
+        )));
+    }
+
+    // One character from each class, the rest from all of them, then shuffled
+    // so the guaranteed characters are not always at the front
+    let alphabet: Vec<u8> = classes.concat();
+    let mut chars = Zeroizing::new(Vec::with_capacity(policy.length));
+    for class in &classes {
+        chars.push(class[uniform(class.len())]);
+    }
+    while chars.len() < policy.length {
+        chars.push(alphabet[uniform(alphabet.len())]);
+    }
+    for i in (1..chars.len()).rev() {
+        chars.swap(i, uniform(i + 1));
+    }
+
+    let password = chars.iter().map(|&c| c as char).collect::<String>();
+    Ok(Zeroizing::new(password))
+}
+
+/// A uniformly distributed index below `bound`, rejecting samples that
+/// would bias the result towards small values
+fn uniform(bound: usize) -> usize {
+    let bound = bound as u32;
+    let limit = u32::MAX - u32::MAX % bound;
+    loop {
+        let sample = OsRng.next_u32();
+        if sample < limit {
+            return (sample % bound) as usize;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_secret_length() {
+        assert_eq!(secret(32).unwrap().len(), 32);
+        assert_ne!(*secret(32).unwrap(), *secret(32).unwrap());
+        assert!(matches!(secret(0), Err(crate::Error::InvalidInput(_))));
+        assert!(matches!(secret(MAX_SECRET_LENGTH + 1), Err(crate::Error::InvalidInput(_))));
+    }
+
+    #[test]
+    fn test_password_policy() {
+        let generated = password(&PasswordPolicy::default()).unwrap();
+        assert_eq!(generated.len(), 24);
+        assert!(generated.bytes().any(|c| LOWERCASE.contains(&c)));
+        assert!(generated.bytes().any(|c| UPPERCASE.contains(&c)));
+        assert!(generated.bytes().any(|c| DIGITS.contains(&c)));
+        assert!(generated.bytes().any(|c| SYMBOLS.contains(&c)));
+
+        let digits_only = PasswordPolicy { length: 6, lowercase: false, uppercase: false, symbols: false, ..Default::default() };
+        assert!(password(&digits_only).unwrap().bytes().all(|c| c.is_ascii_digit()));
+
+        let nothing = PasswordPolicy { digits: false, ..digits_only.clone() };
+        assert!(matches!(password(&nothing), Err(crate::Error::InvalidInput(_))));
+        let too_short = PasswordPolicy { length: 3, ..Default::default() };
+        assert!(matches!(password(&too_short), Err(crate::Error::InvalidInput(_))));
+    }
+}
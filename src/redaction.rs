@@ -0,0 +1,150 @@
+//! Keyed hashing of usernames for log output and audit events.
+//!
+//! A plain digest of a username can be reversed by hashing a list of candidate
+//! usernames, so hashes are keyed with a random value created once per install.
+//! The key stays in the app's data directory and never appears in log output.
+//! It is only read or created the first time a username is hashed, so apps that
+//! never hash one need no writable data directory.
+
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+
+use hmac::{Hmac, Mac};
+use log::LevelFilter;
+use rand_core::{OsRng, RngCore};
+use sha2::Sha256;
+use zeroize::Zeroizing;
+
+const KEY_LEN: usize = 32;
+
+/// The key usernames are hashed with, shared by the keyring and the audit log
+#[derive(Clone)]
+pub struct RedactionKey(Arc<Inner>);
+
+struct Inner {
+    /// Where the key is kept; `None` when the app has no data directory
+    path: Option<PathBuf>,
+    log_level: LevelFilter,
+    /// `None` once loading failed, so usernames are omitted instead of hashed
+    key: OnceLock<Option<Zeroizing<[u8; KEY_LEN]>>>,
+}
+
+impl RedactionKey {
+    /// A fresh random key, kept in memory only
+    pub(crate) fn generate() -> Self {
+        let mut key = Zeroizing::new([0u8; KEY_LEN]);
+        OsRng.fill_bytes(&mut key[..]);
+        Self(Arc::new(Inner { path: None, log_level: LevelFilter::Off, key: OnceLock::from(Some(key)) }))
+    }
+
+    /// The key stored at `path`, read or created when first needed. Failures
+    /// are logged at `log_level` and leave usernames omitted rather than hashed.
+    pub(crate) fn at(path: Option<PathBuf>, log_level: LevelFilter) -> Self {
+        Self(Arc::new(Inner { path, log_level, key: OnceLock::new() }))
+    }
+
+    fn key(&self) -> Option<&Zeroizing<[u8; KEY_LEN]>> {
+        let inner = &*self.0;
+        inner.key.get_or_init(|| {
+            let result = match &inner.path {
+                Some(path) => load_or_create(path, inner.log_level),
+                None => Err(crate::Error::PlatformError("the app has no data directory".into())),
+            };
+            match result {
+                Ok(key) => Some(key),
+                Err(e) => {
+                    keyring_log!(level = inner.log_level, Warn, "Redaction key unavailable, omitting usernames instead of hashing them: {}", e);
+                    None
+                },
+            }
+        }).as_ref()
+    }
+
+    /// A short stable hash identifying a username without revealing it, or
+    /// `None` when the key is unavailable
+    pub(crate) fn hash(&self, username: &str) -> Option<String> {
+        let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(&self.key()?[..])
+            .expect("HMAC accepts keys of any length");
+        mac.update(username.as_bytes());
+        let digest = mac.finalize().into_bytes();
+        let hex: String = digest[..6].iter().map(|byte| format!("{:02x}", byte)).collect();
+        Some(format!("#{}", hex))
+    }
+}
+
+/// Read the key stored at `path`, creating it on first use. A file that does
+/// not hold a key is replaced, which only changes how usernames are hashed.
+fn load_or_create(path: &Path, log_level: LevelFilter) -> crate::Result<Zeroizing<[u8; KEY_LEN]>> {
+    let mut key = Zeroizing::new([0u8; KEY_LEN]);
+    match std::fs::read(path).map(Zeroizing::new) {
+        Ok(bytes) if bytes.len() == KEY_LEN => {
+            key.copy_from_slice(&bytes);
+            return Ok(key);
+        },
+        Ok(_) => {
+            keyring_log!(level = log_level, Warn, "Replacing invalid redaction key at {}", path.display());
+        },
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {},
+        Err(e) => return Err(e.into()),
+    }
+
+    OsRng.fill_bytes(&mut key[..]);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, &key[..])?;
+    Ok(key)
+}
+
+impl std::fmt::Debug for RedactionKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("RedactionKey")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hash_depends_on_the_key() {
+        let key = RedactionKey::generate();
+        let hashed = key.hash("user@example.com").unwrap();
+        assert_eq!(hashed.len(), 13);
+        assert_eq!(Some(&hashed), key.hash("user@example.com").as_ref());
+        assert_ne!(Some(&hashed), key.hash("other@example.com").as_ref());
+        assert_ne!(Some(&hashed), RedactionKey::generate().hash("user@example.com").as_ref());
+    }
+
+    #[test]
+    fn test_key_is_kept_across_loads() {
+        let path = std::env::temp_dir().join(format!("keyring-redaction-{}/redaction.key", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let key_at = || RedactionKey::at(Some(path.clone()), LevelFilter::Off);
+
+        // Nothing is written until a username is hashed
+        let first = key_at();
+        assert!(!path.exists());
+        let hashed = first.hash("alice").unwrap();
+        assert_eq!(key_at().hash("alice").unwrap(), hashed);
+
+        std::fs::write(&path, b"short").unwrap();
+        assert_ne!(key_at().hash("alice").unwrap(), hashed);
+        assert_eq!(std::fs::read(&path).unwrap().len(), KEY_LEN);
+
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_unavailable_key_hashes_nothing() {
+        // A regular file where the key's directory should be
+        let blocker = std::env::temp_dir().join(format!("keyring-redaction-blocker-{}", std::process::id()));
+        std::fs::write(&blocker, b"").unwrap();
+
+        let key = RedactionKey::at(Some(blocker.join("redaction.key")), LevelFilter::Off);
+        assert_eq!(key.hash("alice"), None);
+        assert_eq!(RedactionKey::at(None, LevelFilter::Off).hash("alice"), None);
+
+        std::fs::remove_file(&blocker).unwrap();
+    }
+}
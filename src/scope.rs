@@ -0,0 +1,150 @@
+//! Username scopes granted to a window through its capabilities.
+//!
+//! A capability can narrow a command to matching usernames:
+//!
+//! ```json
+//! { "identifier": "keyring:allow-get-password", "allow": [{ "username": "github:*" }] }
+//! ```
+
+use serde::Deserialize;
+
+/// A username pattern in a capability's `allow` or `deny` list.
+/// `*` matches any run of characters, including none.
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct ScopeEntry {
+    pub username: String,
+}
+
+/// The allowed and denied username patterns in effect for a command
+#[derive(Debug, Default)]
+pub(crate) struct UsernameScope {
+    allow: Vec<String>,
+    deny: Vec<String>,
+}
+
+impl UsernameScope {
+    pub(crate) fn new(allow: Vec<String>, deny: Vec<String>) -> Self {
+        Self { allow, deny }
+    }
+
+    /// A denied pattern always wins. Without any allowed patterns every
+    /// other username is allowed, so capabilities without scopes behave as before.
+    pub(crate) fn is_allowed(&self, username: &str) -> bool {
+        if self.deny.iter().any(|pattern| matches(pattern, username)) {
+            return false;
+        }
+        self.allow.is_empty() || self.allow.iter().any(|pattern| matches(pattern, username))
+    }
+
+    pub(crate) fn check(&self, username: &str) -> crate::Result<()> {
+        if self.is_allowed(username) {
+            Ok(())
+        } else {
+            Err(crate::Error::OutOfScope)
+        }
+    }
+
+    /// Bulk operations act on every username in a service, including ones the
+    /// store cannot list, so they are only allowed when nothing is scoped
+    pub(crate) fn check_unrestricted(&self) -> crate::Result<()> {
+        if self.allow.is_empty() && self.deny.is_empty() {
+            Ok(())
+        } else {
+            Err(crate::Error::OutOfScope)
+        }
+    }
+}
+
+/// The services the frontend may name in a command's `service` argument: the
+/// current service and those the app allows explicitly. Any other name could
+/// point at another application's entries in the platform store.
+#[derive(Debug)]
+pub(crate) struct ServiceScope<'a> {
+    current: &'a str,
+    allowed: &'a [String],
+}
+
+impl<'a> ServiceScope<'a> {
+    pub(crate) fn new(current: &'a str, allowed: &'a [String]) -> Self {
+        Self { current, allowed }
+    }
+
+    pub(crate) fn check(&self, service: &str) -> crate::Result<()> {
+        if service == self.current || self.allowed.iter().any(|allowed| allowed == service) {
+            Ok(())
+        } else {
+            Err(crate::Error::ServiceNotAllowed(service.to_string()))
+        }
+    }
+}
+
+/// Match `text` against a pattern where `*` stands for any run of characters
+fn matches(pattern: &str, text: &str) -> bool {
+    let mut parts = pattern.split('*');
+    // `split` always yields at least one part
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = text.strip_prefix(first) else {
+        return false;
+    };
+
+    let mut parts: Vec<&str> = parts.collect();
+    let Some(last) = parts.pop() else {
+        // No wildcard: the whole text must match
+        return rest.is_empty();
+    };
+    for part in parts {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+    rest.len() >= last.len() && rest.ends_with(last)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pattern_matching() {
+        assert!(matches("github:*", "github:octocat"));
+        assert!(matches("github:*", "github:"));
+        assert!(!matches("github:*", "gitlab:octocat"));
+        assert!(matches("alice", "alice"));
+        assert!(!matches("alice", "alice2"));
+        assert!(matches("*@example.com", "bob@example.com"));
+        assert!(matches("a*b*c", "abc"));
+        assert!(matches("a*b*c", "a-b-b-c"));
+        assert!(!matches("a*b*c", "a-c-b"));
+        assert!(!matches("ab*ba", "aba"));
+        assert!(matches("*", ""));
+    }
+
+    #[test]
+    fn test_username_scope() {
+        assert!(UsernameScope::default().is_allowed("anyone"));
+        assert!(UsernameScope::default().check_unrestricted().is_ok());
+
+        let scope = UsernameScope::new(vec!["github:*".into()], vec!["github:admin".into()]);
+        assert!(scope.is_allowed("github:octocat"));
+        assert!(!scope.is_allowed("github:admin"));
+        assert!(matches!(scope.check("gitlab:octocat"), Err(crate::Error::OutOfScope)));
+
+        let deny_only = UsernameScope::new(vec![], vec!["root".into()]);
+        assert!(deny_only.is_allowed("alice"));
+        assert!(!deny_only.is_allowed("root"));
+        assert!(matches!(deny_only.check_unrestricted(), Err(crate::Error::OutOfScope)));
+        assert!(matches!(scope.check_unrestricted(), Err(crate::Error::OutOfScope)));
+    }
+
+    #[test]
+    fn test_service_scope() {
+        let allowed = vec!["com.example.shared".to_string()];
+        let scope = ServiceScope::new("com.example.app", &allowed);
+        assert!(scope.check("com.example.app").is_ok());
+        assert!(scope.check("com.example.shared").is_ok());
+        // Another application's service, e.g. a browser's Secret Service items
+        assert!(matches!(scope.check("chrome_libsecret_os_crypt_password_v2"), Err(crate::Error::ServiceNotAllowed(_))));
+        assert!(matches!(ServiceScope::new("com.example.app", &[]).check("com.example.shared"), Err(crate::Error::ServiceNotAllowed(_))));
+    }
+}
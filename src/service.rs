@@ -0,0 +1,212 @@
+use std::time::Duration;
+
+use serde::{de::DeserializeOwned, Serialize};
+use tauri::Runtime;
+
+use crate::crypto::SecretKey;
+use crate::implementation::KeyringImplementation;
+use crate::models::*;
+use crate::Keyring;
+
+/// Access to the keyring APIs for a single named service.
+///
+/// Returned by [`Keyring::service`]. Entries set through a handle land in
+/// their own keychain service, separate from the plugin's default service.
+pub struct ServiceHandle<'a, R: Runtime> {
+    keyring: &'a Keyring<R>,
+    service: String,
+}
+
+impl<'a, R: Runtime> ServiceHandle<'a, R> {
+    pub(crate) fn new(keyring: &'a Keyring<R>, service: &str) -> Self {
+        Self {
+            keyring,
+            service: service.to_string(),
+        }
+    }
+
+    fn implementation(&self) -> KeyringImplementation {
+        self.keyring.implementation_for(&self.service)
+    }
+
+    fn notify(&self, operation: ChangeOperation, username: Option<&str>, credential_type: Option<CredentialType>) {
+        self.keyring.notify_changed(&self.service, operation, username, credential_type);
+    }
+
+    /// Report the expired credentials `implementation` removed while reading
+    fn notify_expired(&self, implementation: &KeyringImplementation) {
+        for (username, credential_type) in implementation.take_expired() {
+            self.notify(ChangeOperation::Delete, Some(&username), Some(credential_type));
+        }
+    }
+
+    /// The service name this handle stores entries under
+    pub fn name(&self) -> &str {
+        &self.service
+    }
+
+    pub fn set(
+        &self,
+        username: &str,
+        credential_type: CredentialType,
+        value: CredentialValue,
+    ) -> crate::Result<()> {
+        self.implementation().set(username, credential_type.clone(), value)?;
+        self.notify(ChangeOperation::Set, Some(username), Some(credential_type));
+        Ok(())
+    }
+
+    /// Set a credential that reads as missing, and is removed, once `ttl` has elapsed
+    pub fn set_with_ttl(
+        &self,
+        username: &str,
+        credential_type: CredentialType,
+        value: CredentialValue,
+        ttl: Duration,
+    ) -> crate::Result<()> {
+        self.implementation().set_with_ttl(username, credential_type.clone(), value, ttl)?;
+        self.notify(ChangeOperation::Set, Some(username), Some(credential_type));
+        Ok(())
+    }
+
+    pub fn get(
+        &self,
+        username: &str,
+        credential_type: CredentialType,
+    ) -> crate::Result<CredentialValue> {
+        let implementation = self.implementation();
+        let value = implementation.get(username, credential_type);
+        self.notify_expired(&implementation);
+        value
+    }
+
+    pub fn delete(&self, username: &str, credential_type: CredentialType) -> crate::Result<()> {
+        self.implementation().delete(username, credential_type.clone())?;
+        self.notify(ChangeOperation::Delete, Some(username), Some(credential_type));
+        Ok(())
+    }
+
+    pub fn exists(&self, username: &str, credential_type: CredentialType) -> crate::Result<bool> {
+        let implementation = self.implementation();
+        let exists = implementation.exists(username, credential_type);
+        self.notify_expired(&implementation);
+        exists
+    }
+
+    /// Store `length` random bytes as the user's secret, generated in Rust
+    pub fn generate_secret(&self, username: &str, length: usize) -> crate::Result<()> {
+        self.implementation().generate_secret(username, length)?;
+        self.notify(ChangeOperation::Set, Some(username), Some(CredentialType::Secret));
+        Ok(())
+    }
+
+    /// Store a random password following `policy` as the user's password, generated in Rust
+    pub fn generate_password(&self, username: &str, policy: &PasswordPolicy) -> crate::Result<()> {
+        self.implementation().generate_password(username, policy)?;
+        self.notify(ChangeOperation::Set, Some(username), Some(CredentialType::Password));
+        Ok(())
+    }
+
+    /// A handle for using the secret stored under `username` as a key
+    /// without reading it out
+    pub fn secret_key(&self, username: &str) -> SecretKey {
+        SecretKey::new(self.implementation(), username)
+    }
+
+    /// Get a credential along with its timestamps, label and comment
+    pub fn get_with_metadata(
+        &self,
+        username: &str,
+        credential_type: CredentialType,
+    ) -> crate::Result<CredentialRecord> {
+        let implementation = self.implementation();
+        let record = implementation.get_with_metadata(username, credential_type);
+        self.notify_expired(&implementation);
+        record
+    }
+
+    /// Replace the label and comment of an existing credential
+    pub fn set_metadata(
+        &self,
+        username: &str,
+        credential_type: CredentialType,
+        label: Option<String>,
+        comment: Option<String>,
+    ) -> crate::Result<()> {
+        let implementation = self.implementation();
+        let result = implementation.set_metadata(username, credential_type.clone(), label, comment);
+        self.notify_expired(&implementation);
+        result?;
+        self.notify(ChangeOperation::Set, Some(username), Some(credential_type));
+        Ok(())
+    }
+
+    /// Serialize `value` and store it as a JSON credential
+    pub fn set_json<T: Serialize>(&self, username: &str, value: &T) -> crate::Result<()> {
+        self.implementation().set_json(username, value)?;
+        self.notify(ChangeOperation::Set, Some(username), Some(CredentialType::Json));
+        Ok(())
+    }
+
+    /// Read a JSON credential into `T`, failing with [`Error::InvalidJson`](crate::Error::InvalidJson)
+    /// if the stored payload does not match
+    pub fn get_json<T: DeserializeOwned>(&self, username: &str) -> crate::Result<T> {
+        let implementation = self.implementation();
+        let value = implementation.get_json(username);
+        self.notify_expired(&implementation);
+        value
+    }
+
+    /// Delete every credential type stored for a user
+    pub fn delete_user(&self, username: &str) -> crate::Result<()> {
+        self.implementation().delete_user(username)?;
+        self.notify(ChangeOperation::Delete, Some(username), None);
+        Ok(())
+    }
+
+    /// Delete every credential stored for this service
+    pub fn clear_service(&self) -> crate::Result<()> {
+        self.implementation().clear_service()?;
+        self.notify(ChangeOperation::Delete, None, None);
+        Ok(())
+    }
+
+    /// Delete every expired credential stored for this service, returning how many were removed
+    pub fn purge_expired(&self) -> crate::Result<usize> {
+        let implementation = self.implementation();
+        let purged = implementation.purge_expired();
+        // Reported even if a later removal failed
+        self.notify_expired(&implementation);
+        purged
+    }
+
+    /// List the `(username, credential type)` pairs stored for this service
+    pub fn list(&self) -> crate::Result<Vec<(String, CredentialType)>> {
+        self.implementation().list()
+    }
+
+    /// Move entries stored in the legacy layout to the configured naming scheme.
+    /// Safe to run repeatedly; with `dry_run` nothing is changed.
+    pub fn migrate_naming(&self, dry_run: bool) -> crate::Result<NamingMigration> {
+        let migration = self.implementation().migrate_naming(dry_run)?;
+        if !migration.dry_run {
+            for (username, credential_type) in &migration.moved {
+                self.notify(ChangeOperation::Set, Some(username), Some(credential_type.clone()));
+            }
+        }
+        Ok(migration)
+    }
+
+    /// Rewrite secrets stored by earlier versions, or with another encoding, in the
+    /// configured format. Only listed entries are migrated, so repair the index first
+    /// if it may be incomplete. Returns how many were rewritten.
+    pub fn migrate_encoding(&self) -> crate::Result<usize> {
+        self.implementation().migrate_encoding()
+    }
+
+    /// Rebuild the entry index used for listing by probing the store,
+    /// including every credential type of the given usernames
+    pub fn repair_index(&self, usernames: &[String]) -> crate::Result<Vec<(String, CredentialType)>> {
+        self.implementation().repair_index(usernames)
+    }
+}